@@ -0,0 +1,24 @@
+/* C API for the Advent of Code 2023 solutions, built as a cdylib by `cargo build --release`. */
+#ifndef ADVENT_OF_CODE_2023_H
+#define ADVENT_OF_CODE_2023_H
+
+#include <stdint.h>
+
+#define AOC_OK 0
+#define AOC_ERROR 1
+#define AOC_INVALID_ARGUMENT 2
+
+/*
+ * Solve `part` of the puzzle for `day` of `year` on the null terminated `input`.
+ *
+ * Returns AOC_OK and writes the answer to `out`, or returns AOC_ERROR and writes an
+ * error message to `out`. Either way, the string must be released with aoc_string_free.
+ * Returns AOC_INVALID_ARGUMENT without writing to `out` if a pointer is null or the
+ * input is not valid UTF-8.
+ */
+int32_t aoc_solve(uint16_t year, uint8_t day, uint8_t part, const char *input, char **out);
+
+/* Release a string returned by aoc_solve. Does nothing on NULL. */
+void aoc_string_free(char *s);
+
+#endif
@@ -0,0 +1,186 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// A global allocator that forwards to the system allocator, counting what goes through it
+/// while a [`measure`] is in progress. It needs to be installed by the binary with
+/// `#[global_allocator]`, otherwise all the counts are zero.
+pub struct CountingAllocator;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+/// Bytes currently allocated since the start of the measure, can go below zero
+/// when freeing memory that was allocated before it started.
+static LIVE_BYTES: AtomicI64 = AtomicI64::new(0);
+static PEAK_LIVE_BYTES: AtomicI64 = AtomicI64::new(0);
+/// Held during a [`measure`], as there is only one set of counters.
+static MEASURING: Mutex<()> = Mutex::new(());
+
+fn record_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    let live = LIVE_BYTES.fetch_add(size as i64, Ordering::Relaxed) + size as i64;
+    PEAK_LIVE_BYTES.fetch_max(live, Ordering::Relaxed);
+}
+
+fn record_dealloc(size: usize) {
+    LIVE_BYTES.fetch_sub(size as i64, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if ENABLED.load(Ordering::Relaxed) {
+            record_alloc(layout.size());
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if ENABLED.load(Ordering::Relaxed) {
+            record_alloc(layout.size());
+        }
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if ENABLED.load(Ordering::Relaxed) {
+            record_dealloc(layout.size());
+        }
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if ENABLED.load(Ordering::Relaxed) {
+            record_dealloc(layout.size());
+            record_alloc(new_size);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// What was allocated on the heap while running a closure. This is only what went through
+/// the allocator, not the memory of the process: the stack, the allocator's own overhead and
+/// pages it keeps around after a free are not counted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Number of allocations, reallocations included.
+    pub allocations: u64,
+    /// Total bytes requested, even if they were freed right away.
+    pub allocated_bytes: u64,
+    /// The most bytes that were allocated and not freed yet at the same time, counting from
+    /// the start of the measure.
+    pub peak_live_bytes: u64,
+}
+
+impl fmt::Display for AllocStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations, {} allocated, {} peak live heap",
+            self.allocations,
+            format_bytes(self.allocated_bytes),
+            format_bytes(self.peak_live_bytes)
+        )
+    }
+}
+
+/// Stops counting when dropped, even if the closure measured panics.
+struct Counting;
+
+impl Drop for Counting {
+    fn drop(&mut self) {
+        ENABLED.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Run `f`, counting its allocations. Calls from several threads wait for each other, as
+/// the counters are shared, so `f` must not call `measure` itself. The allocations of the
+/// other threads running at the same time are still counted.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, AllocStats) {
+    // a panic in an earlier measure leaves nothing to clean up
+    let _lock = MEASURING.lock().unwrap_or_else(|e| e.into_inner());
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+    ALLOCATED_BYTES.store(0, Ordering::Relaxed);
+    LIVE_BYTES.store(0, Ordering::Relaxed);
+    PEAK_LIVE_BYTES.store(0, Ordering::Relaxed);
+    ENABLED.store(true, Ordering::SeqCst);
+    let counting = Counting;
+    let res = f();
+    drop(counting);
+    let stats = AllocStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        peak_live_bytes: PEAK_LIVE_BYTES.load(Ordering::Relaxed).max(0) as u64,
+    };
+    (res, stats)
+}
+
+/// Human readable size, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024. && unit < UNITS.len() - 1 {
+        value /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[cfg(test)]
+mod test_alloc {
+    use super::{format_bytes, measure};
+
+    #[test]
+    fn counts_allocations() {
+        let (len, stats) = measure(|| {
+            let first = vec![0u8; 4096];
+            drop(first);
+            let second = vec![1u8; 4096];
+            second.len()
+        });
+        k9::assert_equal!(len, 4096);
+        // other tests run at the same time, so there can be more than what we allocated
+        assert!(stats.allocations >= 2);
+        assert!(stats.allocated_bytes >= 8192);
+        assert!(stats.peak_live_bytes >= 4096);
+    }
+
+    #[test]
+    fn concurrent_measures() {
+        let threads = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    measure(|| {
+                        let block = vec![0u8; 1 << 20];
+                        block.len()
+                    })
+                    .1
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            let stats = thread.join().unwrap();
+            // a measure starting in another thread would reset the counters of this one
+            assert!(stats.allocated_bytes >= 1 << 20);
+            assert!(stats.peak_live_bytes >= 1 << 20);
+        }
+    }
+
+    #[test]
+    fn format_sizes() {
+        k9::assert_equal!(format_bytes(12), "12 B");
+        k9::assert_equal!(format_bytes(1536), "1.5 KiB");
+        k9::assert_equal!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
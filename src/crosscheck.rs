@@ -0,0 +1,252 @@
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::{debug, info};
+
+use crate::fixtures;
+use crate::puzzle_inputs::try_get_puzzle_input;
+use crate::registry::{Answer, Day};
+
+/// Where a crosschecked input comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Real,
+    Example(String),
+    Generated { seed: u64, size: usize },
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Real => write!(f, "puzzle input"),
+            Source::Example(name) => write!(f, "example {name}"),
+            Source::Generated { seed, size } => {
+                write!(f, "generated input (seed {seed}, size {size})")
+            }
+        }
+    }
+}
+
+impl Source {
+    /// A short name for the source, usable in a file name.
+    fn slug(&self) -> String {
+        match self {
+            Source::Real => "real".to_string(),
+            Source::Example(name) => format!("example_{name}"),
+            Source::Generated { seed, size } => format!("seed{seed}_size{size}"),
+        }
+    }
+}
+
+/// Which inputs to run the implementations on.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Also use the puzzle input, if it's cached or can be downloaded.
+    pub real: bool,
+    /// Where to find the examples, see [`fixtures`].
+    pub examples: Option<PathBuf>,
+    /// How many generated inputs to use, with the seeds `0..seeds`.
+    pub seeds: u64,
+    /// Size of the generated inputs.
+    pub size: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            real: true,
+            examples: Some(PathBuf::from("examples")),
+            seeds: 100,
+            size: 10,
+        }
+    }
+}
+
+/// The implementations of a part that did not all give the same answer on an input.
+#[derive(Debug, Clone)]
+pub struct Disagreement {
+    pub day: u8,
+    pub part: u8,
+    pub source: Source,
+    pub input: String,
+    /// The answer of each implementation, or why it has none.
+    pub answers: Vec<(&'static str, Result<Answer, String>)>,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let answers = self
+            .answers
+            .iter()
+            .map(|(name, answer)| match answer {
+                Ok(answer) => format!("{name} = {answer}"),
+                Err(e) => format!("{name} {e}"),
+            })
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "day {} part {} on {}: {}",
+            self.day,
+            self.part,
+            self.source,
+            answers.join(", ")
+        )
+    }
+}
+
+impl Disagreement {
+    /// Write the input to `dir`, returning the path of the file.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Error creating {}", dir.display()))?;
+        let path = dir.join(format!(
+            "day{}_part{}_{}.txt",
+            self.day,
+            self.part,
+            self.source.slug()
+        ));
+        std::fs::write(&path, &self.input)
+            .with_context(|| format!("Error writing {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// The inputs to check `part` of `day` on.
+fn inputs(day: &Day, part: u8, options: &Options) -> Result<Vec<(Source, String)>> {
+    let mut inputs = vec![];
+    if options.real {
+        match try_get_puzzle_input(day.day as i32, 1) {
+            Ok(input) => inputs.push((Source::Real, input)),
+            Err(e) => info!(day = day.day, "skipping the puzzle input: {e:#}"),
+        }
+    }
+    if let Some(root) = &options.examples {
+        for fixture in fixtures::discover(root)? {
+            if fixture.day == day.day && fixture.part == part {
+                let input = std::fs::read_to_string(&fixture.input)
+                    .with_context(|| format!("Error reading {}", fixture.input.display()))?;
+                inputs.push((Source::Example(fixture.name), input));
+            }
+        }
+    }
+    for seed in 0..options.seeds {
+        let source = Source::Generated {
+            seed,
+            size: options.size,
+        };
+        inputs.push((source, day.generate_input(seed, options.size)));
+    }
+    Ok(inputs)
+}
+
+/// Run all the implementations of each part of `day` on the inputs picked by `options`,
+/// returning the inputs they don't agree on. Days with a single implementation per part
+/// have nothing to check.
+pub fn crosscheck(day: &Day, options: &Options) -> Result<Vec<Disagreement>> {
+    let mut disagreements = vec![];
+    for part in [1, 2] {
+        let implementations = day.implementations(part);
+        if implementations.len() < 2 {
+            continue;
+        }
+        for (source, input) in inputs(day, part, options)? {
+            debug!(day = day.day, part, %source, "crosschecking");
+            let answers = implementations
+                .iter()
+                .map(|&(name, solve)| {
+                    let answer = panic::catch_unwind(AssertUnwindSafe(|| solve(&input)))
+                        .map_err(|_| "panicked".to_string());
+                    (name, answer)
+                })
+                .collect::<Vec<_>>();
+            // solutions of the same part can return different integer types
+            let normalized = |answer: &Result<Answer, String>| {
+                answer
+                    .as_ref()
+                    .map(|a| a.to_string())
+                    .map_err(|e| e.clone())
+            };
+            if answers
+                .iter()
+                .any(|(_, answer)| normalized(answer) != normalized(&answers[0].1))
+            {
+                disagreements.push(Disagreement {
+                    day: day.day,
+                    part,
+                    source,
+                    input,
+                    answers,
+                });
+            }
+        }
+    }
+    Ok(disagreements)
+}
+
+#[cfg(all(test, feature = "day9"))]
+mod test_crosscheck {
+    use super::{crosscheck, Options, Source};
+    use crate::registry::{get_day, Day, Implementation};
+
+    fn options() -> Options {
+        Options {
+            real: false,
+            examples: None,
+            seeds: 5,
+            size: 8,
+        }
+    }
+
+    #[test]
+    fn report_and_save_disagreements() {
+        let day = Day {
+            alternatives: &[Implementation {
+                name: "off_by_one",
+                part: 1,
+                solve: |input| (crate::puzzles::day9::part1(input) + 1).into(),
+            }],
+            ..*get_day(9).unwrap()
+        };
+        let disagreements = crosscheck(&day, &options()).unwrap();
+        assert!(!disagreements.is_empty());
+        let first = &disagreements[0];
+        k9::assert_equal!(first.part, 1);
+        assert!(matches!(first.source, Source::Generated { size: 8, .. }));
+        k9::assert_equal!(
+            first
+                .answers
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>(),
+            vec!["main", "off_by_one"]
+        );
+
+        let dir = std::env::temp_dir().join(format!("aoc_crosscheck_{}", std::process::id()));
+        let path = first.save(&dir).unwrap();
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("day9_part1_seed"));
+        k9::assert_equal!(std::fs::read_to_string(&path).unwrap(), first.input);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn panics_are_disagreements() {
+        let day = Day {
+            alternatives: &[Implementation {
+                name: "todo",
+                part: 2,
+                solve: |_| todo!(),
+            }],
+            ..*get_day(9).unwrap()
+        };
+        let disagreements = crosscheck(&day, &options()).unwrap();
+        k9::assert_equal!(disagreements.len(), 5);
+        assert!(disagreements[0].to_string().contains("todo panicked"));
+    }
+}
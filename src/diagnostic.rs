@@ -0,0 +1,113 @@
+use crate::utils::parse::ParseError;
+
+/// The parse error behind `error`, if it comes from one.
+pub fn parse_error(error: &anyhow::Error) -> Option<&ParseError> {
+    error.chain().find_map(|e| e.downcast_ref::<ParseError>())
+}
+
+/// Render `error` the way compilers do, quoting the line of `source` it's about:
+///
+/// ```text
+/// error: unknown color `purple`
+///  --> inputs/day_2.txt:2:11
+///   |
+/// 2 | Game 2: 4 purple
+///   |           ^^^^^^
+///   = hint: the cubes are red, green or blue
+/// ```
+///
+/// `file` is only used to say where `source` comes from.
+pub fn render(error: &ParseError, source: &str, file: &str) -> String {
+    let number = error.line.to_string();
+    let gutter = " ".repeat(number.len());
+    let mut res = format!(
+        "error: {}\n{gutter}--> {file}:{}:{}\n{gutter} |\n",
+        error.message, error.line, error.column
+    );
+    // the error can be right after the last line, e.g. when something is missing
+    if let Some(line) = source.lines().nth(error.line - 1) {
+        let line = line.trim_end();
+        // keep the carets under the right chars when the line is indented with tabs
+        let padding = line
+            .chars()
+            .take(error.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        res += &format!(
+            "{number} | {line}\n{gutter} | {padding}{}\n",
+            "^".repeat(error.len.max(1))
+        );
+    }
+    if let Some(hint) = &error.hint {
+        res += &format!("{gutter} = hint: {hint}\n");
+    }
+    res
+}
+
+#[cfg(test)]
+mod test_diagnostic {
+    use super::{parse_error, render};
+    use crate::utils::parse::Text;
+    use anyhow::Context;
+
+    const INPUT: &str = "\
+Game 1: 3 blue, 4 red
+Game 2: 4 purple
+";
+
+    #[test]
+    fn render_with_hint() {
+        let line = Text::new(INPUT).lines().nth(1).unwrap();
+        let error = line
+            .slice(10..16)
+            .error("unknown color `purple`")
+            .with_hint("the cubes are red, green or blue");
+        k9::snapshot!(
+            render(&error, INPUT, "inputs/day_2.txt"),
+            "
+error: unknown color `purple`
+ --> inputs/day_2.txt:2:11
+  |
+2 | Game 2: 4 purple
+  |           ^^^^^^
+  = hint: the cubes are red, green or blue
+
+"
+        );
+    }
+
+    #[test]
+    fn render_past_the_end() {
+        let input = (1..=10).map(|i| format!("{i}\n")).collect::<String>();
+        let error = Text::new(&input).trim().end().error("missing line 11");
+        k9::snapshot!(
+            render(&error, &input, "<stdin>"),
+            "
+error: missing line 11
+  --> <stdin>:10:3
+   |
+10 | 10
+   |   ^
+
+"
+        );
+        let error = Text::new(&input).end().error("missing line 11");
+        k9::snapshot!(
+            render(&error, &input, "<stdin>"),
+            "
+error: missing line 11
+  --> <stdin>:11:1
+   |
+
+"
+        );
+    }
+
+    #[test]
+    fn find_the_parse_error() {
+        let error = anyhow::Error::new(Text::new(INPUT).error("nope")).context("Parsing day 2");
+        k9::assert_equal!(parse_error(&error).map(|e| e.line), Some(1));
+        let error: anyhow::Result<()> = Err(anyhow::anyhow!("not a parse error"));
+        assert!(parse_error(&error.context("Parsing").unwrap_err()).is_none());
+    }
+}
@@ -0,0 +1,59 @@
+//! C API to embed the solutions in other programs, see `include/advent_of_code_2023.h`.
+//!
+//! Answers and error messages are returned as strings allocated by this library,
+//! which must be released with [`aoc_string_free`].
+
+use std::ffi::{c_char, CStr, CString};
+
+use crate::solve;
+
+/// The answer was written to `out`.
+pub const AOC_OK: i32 = 0;
+/// The puzzle could not be solved, an error message was written to `out`.
+pub const AOC_ERROR: i32 = 1;
+/// The arguments were invalid (null pointers or input not in UTF-8), nothing was written to `out`.
+pub const AOC_INVALID_ARGUMENT: i32 = 2;
+
+/// Solve `part` of the puzzle for `day` of `year` on the null terminated `input`.
+///
+/// On success, the answer is written to `out` as a null terminated string and `AOC_OK`
+/// is returned. On failure an error message is written to `out` instead and `AOC_ERROR`
+/// is returned. In both cases the string must be released with `aoc_string_free`.
+///
+/// # Safety
+///
+/// `input` must be a valid null terminated string, and `out` a valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_solve(
+    year: u16,
+    day: u8,
+    part: u8,
+    input: *const c_char,
+    out: *mut *mut c_char,
+) -> i32 {
+    if input.is_null() || out.is_null() {
+        return AOC_INVALID_ARGUMENT;
+    }
+    let Ok(input) = CStr::from_ptr(input).to_str() else {
+        return AOC_INVALID_ARGUMENT;
+    };
+    let (status, message) = match solve(year, day, part, input) {
+        Ok(answer) => (AOC_OK, answer.to_string()),
+        Err(e) => (AOC_ERROR, e.to_string()),
+    };
+    // our messages never contain null bytes
+    *out = CString::new(message).unwrap().into_raw();
+    status
+}
+
+/// Release a string returned by this library. Does nothing on null pointers.
+///
+/// # Safety
+///
+/// `s` must have been returned by this library, and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// An example input with a known answer, found under `examples/`.
+///
+/// Each day has its own directory, `examples/day{day}/`, holding the inputs as `{name}.txt`
+/// and the expected answers in `part1.toml` and `part2.toml`, e.g. `example = 114`.
+/// An input can be listed for one part only, or for both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    pub day: u8,
+    pub part: u8,
+    pub name: String,
+    pub input: PathBuf,
+    pub expected: String,
+}
+
+impl fmt::Display for Fixture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {} part {} {}", self.day, self.part, self.name)
+    }
+}
+
+impl Fixture {
+    /// Run the solution on the fixture input, and compare it to the expected answer.
+    pub fn check(&self) -> Result<()> {
+        let input = std::fs::read_to_string(&self.input)
+            .with_context(|| format!("Error reading {}", self.input.display()))?;
+        let answer = crate::solve(2023, self.day, self.part, &input)?.to_string();
+        if answer != self.expected {
+            bail!("expected {}, got {answer}", self.expected);
+        }
+        Ok(())
+    }
+}
+
+/// The day of a fixture directory, e.g. 5 for `day5`.
+fn parse_day_dir(path: &Path) -> Option<u8> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("day")?
+        .parse()
+        .ok()
+}
+
+fn read_answers(dir: &Path, day: u8, part: u8) -> Result<Vec<Fixture>> {
+    let path = dir.join(format!("part{part}.toml"));
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Error reading {}", path.display()))?;
+    let answers: toml::Table = content
+        .parse()
+        .with_context(|| format!("Invalid answers file {}", path.display()))?;
+
+    answers
+        .into_iter()
+        .map(|(name, value)| {
+            let expected = match value {
+                toml::Value::Integer(v) => v.to_string(),
+                // answers that don't fit in an i64 can be written as strings
+                toml::Value::String(v) => v,
+                other => bail!("Invalid answer for {name} in {}: {other}", path.display()),
+            };
+            let input = dir.join(format!("{name}.txt"));
+            if !input.is_file() {
+                bail!(
+                    "{} has an answer for {name}, but {} is missing",
+                    path.display(),
+                    input.display()
+                );
+            }
+            Ok(Fixture {
+                day,
+                part,
+                name,
+                input,
+                expected,
+            })
+        })
+        .collect()
+}
+
+/// Find all the fixtures under `root`, sorted by day, part and name.
+pub fn discover(root: &Path) -> Result<Vec<Fixture>> {
+    let entries =
+        std::fs::read_dir(root).with_context(|| format!("Error reading {}", root.display()))?;
+    let mut fixtures = vec![];
+    for entry in entries {
+        let dir = entry?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let day =
+            parse_day_dir(&dir).ok_or_else(|| anyhow!("Unexpected directory {}", dir.display()))?;
+        for part in [1, 2] {
+            fixtures.extend(read_answers(&dir, day, part)?);
+        }
+    }
+    fixtures.sort_by(|a, b| (a.day, a.part, &a.name).cmp(&(b.day, b.part, &b.name)));
+    Ok(fixtures)
+}
+
+#[cfg(test)]
+mod test_fixtures {
+    use super::discover;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc_fixtures_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("day9")).unwrap();
+        dir
+    }
+
+    #[test]
+    #[cfg(feature = "day9")]
+    fn discover_and_check() {
+        use super::Fixture;
+
+        let root = temp_dir("ok");
+        let day = root.join("day9");
+        std::fs::write(day.join("small.txt"), "1 2 3\n").unwrap();
+        std::fs::write(day.join("part1.toml"), "small = 4\n").unwrap();
+        std::fs::write(day.join("part2.toml"), "small = \"1\"\n").unwrap();
+
+        let fixtures = discover(&root).unwrap();
+        k9::assert_equal!(
+            fixtures,
+            vec![
+                Fixture {
+                    day: 9,
+                    part: 1,
+                    name: "small".to_string(),
+                    input: day.join("small.txt"),
+                    expected: "4".to_string(),
+                },
+                Fixture {
+                    day: 9,
+                    part: 2,
+                    name: "small".to_string(),
+                    input: day.join("small.txt"),
+                    expected: "1".to_string(),
+                },
+            ]
+        );
+        assert!(fixtures[0].check().is_ok());
+        assert!(fixtures[1].check().is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn missing_input_is_an_error() {
+        let root = temp_dir("missing");
+        std::fs::write(root.join("day9").join("part1.toml"), "nope = 4\n").unwrap();
+        assert!(discover(&root).is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
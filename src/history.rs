@@ -0,0 +1,359 @@
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+
+/// What tells inputs apart, without keeping them in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// FNV-1a hash of the input, which unlike the std hasher is the same across builds.
+    pub hash: u64,
+    pub len: u64,
+}
+
+impl Fingerprint {
+    pub fn of(input: &str) -> Fingerprint {
+        let hash = input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        Fingerprint {
+            hash,
+            len: input.len() as u64,
+        }
+    }
+}
+
+/// The benchmark of one implementation of a part, at a given commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub commit: String,
+    /// Whether there were uncommitted changes when the benchmark ran.
+    pub dirty: bool,
+    /// The input the benchmark ran on, unknown for the records from before it was kept.
+    pub input: Option<Fingerprint>,
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub day: u8,
+    pub part: u8,
+    pub implementation: String,
+    pub iterations: u32,
+    pub median: Duration,
+    pub min: Duration,
+}
+
+impl Record {
+    /// A record of `times` measured now on `input`, at a commit for the caller to fill in.
+    pub fn new(day: u8, part: u8, implementation: &str, input: &str, times: &[Duration]) -> Record {
+        let mut times = times.to_vec();
+        times.sort();
+        Record {
+            commit: String::new(),
+            dirty: false,
+            input: Some(Fingerprint::of(input)),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            day,
+            part,
+            implementation: implementation.to_string(),
+            iterations: times.len() as u32,
+            median: times[times.len() / 2],
+            min: times[0],
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "commit": self.commit,
+            "dirty": self.dirty,
+            "input_hash": self.input.map(|i| i.hash),
+            "input_len": self.input.map(|i| i.len),
+            "timestamp": self.timestamp,
+            "day": self.day,
+            "part": self.part,
+            "implementation": self.implementation,
+            "iterations": self.iterations,
+            "median_ns": self.median.as_nanos() as u64,
+            "min_ns": self.min.as_nanos() as u64,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Record> {
+        let field = |name: &str| {
+            value
+                .get(name)
+                .ok_or_else(|| anyhow!("Missing field {name}"))
+        };
+        let uint = |name: &str| {
+            field(name)?
+                .as_u64()
+                .ok_or_else(|| anyhow!("Field {name} should be an unsigned integer"))
+        };
+        let string = |name: &str| {
+            field(name)?
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| anyhow!("Field {name} should be a string"))
+        };
+        let input = match (value.get("input_hash"), value.get("input_len")) {
+            (None | Some(Value::Null), None | Some(Value::Null)) => None,
+            _ => Some(Fingerprint {
+                hash: uint("input_hash")?,
+                len: uint("input_len")?,
+            }),
+        };
+        Ok(Record {
+            commit: string("commit")?,
+            dirty: field("dirty")?
+                .as_bool()
+                .ok_or_else(|| anyhow!("Field dirty should be a boolean"))?,
+            input,
+            timestamp: uint("timestamp")?,
+            day: uint("day")?.try_into()?,
+            part: uint("part")?.try_into()?,
+            implementation: string("implementation")?,
+            iterations: uint("iterations")?.try_into()?,
+            median: Duration::from_nanos(uint("median_ns")?),
+            min: Duration::from_nanos(uint("min_ns")?),
+        })
+    }
+
+    /// Whether both records timed the same code on the same input. Records of an unknown
+    /// input can't be compared to anything.
+    fn same_benchmark(&self, other: &Record) -> bool {
+        self.input.is_some()
+            && (self.day, self.part, &self.implementation, self.input)
+                == (other.day, other.part, &other.implementation, other.input)
+    }
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Error running git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The full hash of a git revision, e.g. `HEAD~2` or a branch name.
+pub fn resolve(rev: &str) -> Result<String> {
+    git(&["rev-parse", "--verify", &format!("{rev}^{{commit}}")])
+        .with_context(|| format!("Unknown revision {rev}"))
+}
+
+/// The commit the tree is at, and whether it has uncommitted changes.
+pub fn current_commit() -> Result<(String, bool)> {
+    let commit = resolve("HEAD")?;
+    let dirty = !git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty();
+    Ok((commit, dirty))
+}
+
+/// All the records in the history file at `path`, oldest first. A missing file is an empty history.
+pub fn load(path: &Path) -> Result<Vec<Record>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Error reading {}", path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map_err(anyhow::Error::from)
+                .and_then(|value| Record::from_json(&value))
+                .with_context(|| format!("Invalid record on line {} of {}", i + 1, path.display()))
+        })
+        .collect()
+}
+
+/// Add `records` at the end of the history file at `path`, creating it if needed.
+pub fn append(path: &Path, records: &[Record]) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Error opening {}", path.display()))?;
+    for record in records {
+        writeln!(file, "{}", record.to_json())?;
+    }
+    Ok(())
+}
+
+/// How a benchmark changed since an earlier commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub before: Record,
+    pub after: Record,
+}
+
+impl Comparison {
+    /// Relative change of the median time, e.g. `0.25` when it got 25% slower.
+    pub fn change(&self) -> f64 {
+        self.after.median.as_secs_f64() / self.before.median.as_secs_f64() - 1.
+    }
+
+    /// Whether the median got slower by more than `threshold`, e.g. `0.1` for 10%.
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.change() > threshold
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Day {} part {} ({}): {:?} -> {:?} ({:+.1}%)",
+            self.after.day,
+            self.after.part,
+            self.after.implementation,
+            self.before.median,
+            self.after.median,
+            self.change() * 100.
+        )
+    }
+}
+
+/// Compare the `current` records to the latest ones of the same benchmarks at `commit`.
+/// Runs on uncommitted changes are only used when there is no clean run, as they timed
+/// something else than the commit. Benchmarks that never ran at `commit` are left out.
+pub fn compare(history: &[Record], commit: &str, current: &[Record]) -> Vec<Comparison> {
+    current
+        .iter()
+        .filter_map(|after| {
+            let runs = history
+                .iter()
+                .rev()
+                .filter(|r| r.commit == commit && r.same_benchmark(after));
+            let before = runs.clone().find(|r| !r.dirty).or(runs.clone().next())?;
+            Some(Comparison {
+                before: before.clone(),
+                after: after.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::{append, compare, load, Fingerprint, Record};
+    use std::time::Duration;
+
+    fn record(commit: &str, part: u8, median_ms: u64) -> Record {
+        Record {
+            commit: commit.to_string(),
+            dirty: false,
+            input: Some(Fingerprint::of("input")),
+            timestamp: 1_700_000_000,
+            day: 5,
+            part,
+            implementation: "main".to_string(),
+            iterations: 10,
+            median: Duration::from_millis(median_ms),
+            min: Duration::from_millis(median_ms / 2),
+        }
+    }
+
+    #[test]
+    fn append_and_load() {
+        let path = std::env::temp_dir().join(format!("aoc_history_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        k9::assert_equal!(load(&path).unwrap(), vec![]);
+
+        let records = vec![record("abc", 1, 10), record("abc", 2, 20)];
+        append(&path, &records[..1]).unwrap();
+        append(&path, &records[1..]).unwrap();
+        k9::assert_equal!(load(&path).unwrap(), records);
+
+        // the records from before the inputs were kept
+        let mut old = records[0].to_json();
+        old.as_object_mut().unwrap().remove("input_hash");
+        old.as_object_mut().unwrap().remove("input_len");
+        std::fs::write(&path, format!("{old}\n")).unwrap();
+        k9::assert_equal!(load(&path).unwrap()[0].input, None);
+
+        std::fs::write(&path, "{\"commit\": \"abc\"}\n").unwrap();
+        assert!(load(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn new_record_takes_the_median() {
+        let times = [3, 1, 2].map(Duration::from_millis);
+        let record = Record::new(5, 1, "main", "input", &times);
+        k9::assert_equal!(record.median, Duration::from_millis(2));
+        k9::assert_equal!(record.min, Duration::from_millis(1));
+        k9::assert_equal!(record.iterations, 3);
+    }
+
+    #[test]
+    fn compare_to_latest_run_of_a_commit() {
+        let history = vec![
+            record("old", 1, 10),
+            record("old", 1, 20),
+            record("other", 2, 10),
+            record("new", 1, 100),
+        ];
+        let current = vec![record("new", 1, 25), record("new", 2, 10)];
+        let comparisons = compare(&history, "old", &current);
+        k9::assert_equal!(comparisons.len(), 1);
+        k9::assert_equal!(comparisons[0].before.median, Duration::from_millis(20));
+        assert!((comparisons[0].change() - 0.25).abs() < 1e-9);
+        assert!(comparisons[0].is_regression(0.1));
+        assert!(!comparisons[0].is_regression(0.3));
+        k9::assert_equal!(
+            comparisons[0].to_string(),
+            "Day 5 part 1 (main): 20ms -> 25ms (+25.0%)"
+        );
+    }
+
+    #[test]
+    fn compare_clean_runs_on_the_same_input() {
+        let dirty = |median_ms| Record {
+            dirty: true,
+            ..record("old", 1, median_ms)
+        };
+        let other_input = Record {
+            input: Some(Fingerprint::of("other input")),
+            ..record("old", 1, 30)
+        };
+        let current = vec![record("new", 1, 25)];
+
+        let history = vec![record("old", 1, 20), dirty(40), other_input.clone()];
+        let comparisons = compare(&history, "old", &current);
+        k9::assert_equal!(comparisons.len(), 1);
+        k9::assert_equal!(comparisons[0].before.median, Duration::from_millis(20));
+
+        // better than nothing, the caller can tell from the record
+        let comparisons = compare(&[dirty(40)], "old", &current);
+        assert!(comparisons[0].before.dirty);
+
+        assert!(compare(&[other_input], "old", &current).is_empty());
+        let unknown_input = Record {
+            input: None,
+            ..record("old", 1, 20)
+        };
+        assert!(compare(&[unknown_input], "old", &current).is_empty());
+    }
+
+    #[test]
+    fn fingerprints() {
+        k9::assert_equal!(Fingerprint::of("").hash, 0xcbf2_9ce4_8422_2325);
+        k9::assert_equal!(Fingerprint::of("a").hash, 0xaf63_dc4c_8601_ec8c);
+        k9::assert_equal!(Fingerprint::of("abc").len, 3);
+        assert!(Fingerprint::of("ab") != Fingerprint::of("ba"));
+    }
+}
@@ -0,0 +1,227 @@
+use std::any::Any;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::alloc;
+use crate::registry::Solver;
+
+/// How running a solution went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Solved {
+        answer: String,
+        elapsed: Duration,
+        /// What was allocated, when it was measured.
+        allocations: Option<String>,
+    },
+    /// The solution panicked, with this message.
+    Panicked(String),
+    TimedOut(Duration),
+    StackOverflow,
+    /// The process running the solution died in some other way.
+    Crashed(String),
+}
+
+impl Status {
+    pub fn is_solved(&self) -> bool {
+        matches!(self, Status::Solved { .. })
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Solved {
+                answer, elapsed, ..
+            } => write!(f, "{answer} ({elapsed:?})"),
+            Status::Panicked(message) => write!(f, "panicked: {message}"),
+            Status::TimedOut(timeout) => write!(f, "timed out after {timeout:?}"),
+            Status::StackOverflow => write!(f, "stack overflow"),
+            Status::Crashed(reason) => write!(f, "crashed: {reason}"),
+        }
+    }
+}
+
+/// The message given to `panic!`, when it's a string.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Run the solution in the current thread, catching it if it panics.
+pub fn solve(solver: Solver, input: &str, mem: bool) -> Status {
+    let start = Instant::now();
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        if mem {
+            let (answer, stats) = alloc::measure(|| solver(input));
+            (answer, Some(stats.to_string()))
+        } else {
+            (solver(input), None)
+        }
+    }));
+    match res {
+        Ok((answer, allocations)) => Status::Solved {
+            answer: answer.to_string(),
+            elapsed: start.elapsed(),
+            allocations,
+        },
+        Err(payload) => Status::Panicked(panic_message(payload.as_ref())),
+    }
+}
+
+/// Run the solution in its own thread, so we can give up on it after the timeout.
+/// There is no way to kill a thread, so a solution that times out keeps running
+/// in the background until it finishes on its own. A stack overflow still aborts
+/// the whole process, use [`run_in_subprocess`] to survive those.
+pub fn run_in_thread(solver: Solver, input: String, timeout: Duration) -> Status {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(solve(solver, &input, false));
+    });
+    receiver
+        .recv_timeout(timeout)
+        .unwrap_or(Status::TimedOut(timeout))
+}
+
+/// Run the solution in the current process, and write how it went to stdout for
+/// [`run_in_subprocess`] to read. This is what the child process should call.
+pub fn report(solver: Solver, input: &str, mem: bool) -> Result<()> {
+    let line = match solve(solver, input, mem) {
+        Status::Solved {
+            answer,
+            elapsed,
+            allocations,
+        } => format!(
+            "solved\t{answer}\t{}\t{}",
+            elapsed.as_nanos(),
+            allocations.unwrap_or_default()
+        ),
+        Status::Panicked(message) => format!("panicked\t{}", message.replace('\n', " ")),
+        other => bail!("Unexpected status {other}"),
+    };
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{line}")?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn parse_report(line: &str) -> Result<Status> {
+    let fields = line.trim_end_matches('\n').split('\t').collect::<Vec<_>>();
+    match fields.as_slice() {
+        ["solved", answer, nanos, allocations] => Ok(Status::Solved {
+            answer: answer.to_string(),
+            elapsed: Duration::from_nanos(nanos.parse()?),
+            allocations: Some(allocations.to_string()).filter(|a| !a.is_empty()),
+        }),
+        ["panicked", message] => Ok(Status::Panicked(message.to_string())),
+        _ => Err(anyhow!("Invalid report {line:?}")),
+    }
+}
+
+/// Run `command`, a process calling [`report`] on the input it reads from stdin, killing it
+/// after the timeout. Its stderr is passed through, and watched for stack overflows.
+pub fn run_in_subprocess(mut command: Command, input: &str, timeout: Duration) -> Result<Status> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Error starting the solution process")?;
+
+    // read the outputs in the background, so that the child never blocks on a full pipe
+    let mut stdout = child.stdout.take().unwrap();
+    let stdout = std::thread::spawn(move || {
+        let mut out = String::new();
+        let _ = stdout.read_to_string(&mut out);
+        out
+    });
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+    let stderr = std::thread::spawn(move || {
+        let mut overflowed = false;
+        for line in stderr.lines().map_while(|l| l.ok()) {
+            overflowed |= line.contains("has overflowed its stack");
+            eprintln!("{line}");
+        }
+        overflowed
+    });
+    // the child can die before reading all of it, which is reported below
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+
+    let start = Instant::now();
+    let exit = loop {
+        if let Some(exit) = child.try_wait()? {
+            break exit;
+        }
+        if start.elapsed() > timeout {
+            child.kill()?;
+            child.wait()?;
+            return Ok(Status::TimedOut(timeout));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    };
+
+    let out = stdout.join().unwrap_or_default();
+    let overflowed = stderr.join().unwrap_or_default();
+    if overflowed {
+        return Ok(Status::StackOverflow);
+    }
+    match out.lines().next() {
+        Some(line) if exit.success() => parse_report(line),
+        _ => Ok(Status::Crashed(exit.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test_isolate {
+    use super::{parse_report, Status};
+    use std::time::Duration;
+
+    #[test]
+    #[cfg(feature = "day9")]
+    fn thread_statuses() {
+        use super::run_in_thread;
+        use crate::registry::get_day;
+
+        let solver = get_day(9).unwrap().part1;
+        let status = run_in_thread(solver, "1 2 3\n".to_string(), Duration::from_secs(5));
+        assert!(matches!(&status, Status::Solved { answer, .. } if answer == "4"));
+
+        let status = run_in_thread(solver, "x".to_string(), Duration::from_secs(5));
+        assert!(matches!(status, Status::Panicked(_)));
+
+        let slow = |_: &str| {
+            std::thread::sleep(Duration::from_secs(1));
+            0.into()
+        };
+        let status = run_in_thread(slow, String::new(), Duration::from_millis(10));
+        k9::assert_equal!(status, Status::TimedOut(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn parse_reports() {
+        k9::assert_equal!(
+            parse_report("solved\t42\t1000\t\n").unwrap(),
+            Status::Solved {
+                answer: "42".to_string(),
+                elapsed: Duration::from_micros(1),
+                allocations: None,
+            }
+        );
+        k9::assert_equal!(
+            parse_report("panicked\tnot yet implemented").unwrap(),
+            Status::Panicked("not yet implemented".to_string())
+        );
+        assert!(parse_report("garbage").is_err());
+    }
+}
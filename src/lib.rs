@@ -1,3 +1,72 @@
+//! Solutions to the [Advent of Code 2023](https://adventofcode.com/2023) puzzles.
+//!
+//! The easiest way to use the solutions is [`solve`], which works the same way for every day:
+//!
+//! ```
+//! # #[cfg(feature = "day9")] {
+//! use advent_of_code_2023::{solve, Answer};
+//!
+//! let input = "0 3 6 9 12 15\n1 3 6 10 15 21\n10 13 16 21 30 45\n";
+//! assert_eq!(solve(2023, 9, 1, input).unwrap(), Answer::Signed(114));
+//! # }
+//! ```
+//!
+//! The same entry point is exposed to other languages through the C API in [`ffi`].
+
+pub mod alloc;
+pub mod crosscheck;
+pub mod diagnostic;
+pub mod ffi;
+pub mod fixtures;
+pub mod history;
+pub mod isolate;
 pub mod puzzle_inputs;
 pub mod puzzles;
+pub mod registry;
+pub mod repl;
+pub mod serve;
+pub mod shrink;
 pub mod utils;
+pub mod watch;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use anyhow::{anyhow, bail, Result};
+
+pub use registry::Answer;
+
+/// Solve `part` (1 or 2) of the puzzle for `day` of `year` on `input`.
+///
+/// Only 2023 is supported. Errors if the day is not solved, if the part is invalid,
+/// or if the solution panicked, which usually means the input is malformed.
+pub fn solve(year: u16, day: u8, part: u8, input: &str) -> Result<Answer> {
+    if year != 2023 {
+        bail!("Only the 2023 puzzles are solved, not {year}");
+    }
+    let solved_day = registry::lookup(day)?;
+    let solver = solved_day
+        .part(part)
+        .ok_or_else(|| anyhow!("Invalid part {part}, should be 1 or 2"))?;
+    panic::catch_unwind(AssertUnwindSafe(|| solver(input)))
+        .map_err(|_| anyhow!("The solution for day {day} part {part} panicked on this input"))
+}
+
+#[cfg(all(test, feature = "day9"))]
+mod test_solve {
+    use super::{solve, Answer};
+
+    const DAY_9_EXAMPLE: &str = "0 3 6 9 12 15\n1 3 6 10 15 21\n10 13 16 21 30 45\n";
+
+    #[test]
+    fn solve_example() {
+        k9::assert_equal!(solve(2023, 9, 2, DAY_9_EXAMPLE).unwrap(), Answer::Signed(2));
+    }
+
+    #[test]
+    fn solve_errors() {
+        assert!(solve(2022, 9, 1, DAY_9_EXAMPLE).is_err());
+        assert!(solve(2023, 30, 1, DAY_9_EXAMPLE).is_err());
+        assert!(solve(2023, 9, 3, DAY_9_EXAMPLE).is_err());
+        assert!(solve(2023, 9, 1, "not a number").is_err());
+    }
+}
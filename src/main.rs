@@ -1,16 +1,43 @@
-mod puzzle_inputs;
+use std::hint::black_box;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
 
-use crate::puzzle_inputs::get_puzzle_input;
+use advent_of_code_2023::alloc::{self, CountingAllocator};
+use advent_of_code_2023::isolate::{self, Status};
+use advent_of_code_2023::puzzle_inputs::{read_input, try_get_puzzle_input};
+use advent_of_code_2023::registry::{self, Solver};
+use advent_of_code_2023::{crosscheck, diagnostic, history, repl, serve, shrink, watch};
 
-use clap::{Parser, Subcommand};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use colored::Colorize;
+use tracing::{warn, Level};
+
+/// Only counts while `--mem` measures a solution, otherwise it's the system allocator.
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Show what the solutions are doing, `-v` for debug output and `-vv` for trace output.
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Failure {
+    /// The implementations of the part give different answers.
+    Disagree,
+    /// The main implementation of the part panics.
+    Panic,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Ensures that the input files for the current day are cached locally.
@@ -18,16 +45,578 @@ enum Commands {
         /// The day to cache the input for.
         day: u8,
     },
+    /// Runs the solutions for a day and prints the answers.
+    ///
+    /// Each part runs in its own process, so that one panicking, overflowing its stack or
+    /// taking too long is reported without stopping the other ones.
+    Run {
+        /// The day to run. All the days are run by default.
+        day: Option<u8>,
+        /// Only run this part. Both parts are run by default.
+        #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: Option<u8>,
+        /// Read the input from this file instead of the cached puzzle input. Use `-` for stdin.
+        #[arg(short, long, requires = "day")]
+        input: Option<PathBuf>,
+        /// Report the allocations made by each part.
+        #[arg(long)]
+        mem: bool,
+        /// Give up on a part after this many seconds.
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+    },
+    /// Solves a part on the input read from stdin and reports how it went, for `run`.
+    #[command(hide = true)]
+    Isolated {
+        day: u8,
+        part: u8,
+        #[arg(long)]
+        mem: bool,
+    },
+    /// Runs the solutions for a day several times and reports how long they take.
+    Bench {
+        /// The day to benchmark.
+        day: u8,
+        /// Only benchmark this part. Both parts are benchmarked by default.
+        #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: Option<u8>,
+        /// Read the input from this file instead of the cached puzzle input. Use `-` for stdin.
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        /// How many times to run each part.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        iterations: u32,
+        /// Report the allocations made by each part.
+        #[arg(long)]
+        mem: bool,
+        /// Benchmark this implementation of the parts instead of the main one.
+        #[arg(short = 'I', long, default_value = "main")]
+        implementation: String,
+        /// Compare the timings to the ones recorded at this git revision.
+        #[arg(long)]
+        compare: Option<String>,
+        /// Report a regression when a part gets slower than this, in percent.
+        #[arg(long, default_value_t = 10.)]
+        threshold: f64,
+        /// Where the timings are recorded.
+        #[arg(long, default_value = "bench_history.jsonl")]
+        history: PathBuf,
+    },
+    /// Prints a random input for a day, e.g. to benchmark a solution on a bigger input.
+    Generate {
+        /// The day to generate an input for.
+        day: u8,
+        /// The same seed always gives the same input.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Roughly how many lines or columns the input has.
+        #[arg(long, default_value_t = 100)]
+        size: usize,
+    },
+    /// Runs all the implementations of each part on the same inputs, and reports where they disagree.
+    Crosscheck {
+        /// Only check this day. All the days are checked by default.
+        day: Option<u8>,
+        /// How many generated inputs to check.
+        #[arg(long, default_value_t = 100)]
+        seeds: u64,
+        /// Roughly how many lines or columns the generated inputs have.
+        #[arg(long, default_value_t = 10)]
+        size: usize,
+        /// Skip the puzzle inputs, brute force implementations can be slow on them.
+        #[arg(long)]
+        no_real: bool,
+        /// Where to save the inputs the implementations disagree on.
+        #[arg(long, default_value = "crosscheck")]
+        out: PathBuf,
+        /// Also save a shrunk version of each input the implementations disagree on.
+        #[arg(long)]
+        shrink: bool,
+    },
+    /// Shrinks an input a part fails on to a minimal one that still fails, and saves it.
+    Shrink {
+        /// The day that fails.
+        day: u8,
+        /// The part that fails.
+        #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: u8,
+        /// The failing input. Use `-` for stdin.
+        #[arg(short, long)]
+        input: PathBuf,
+        /// What counts as a failure.
+        #[arg(long, value_enum, default_value_t = Failure::Disagree)]
+        until: Failure,
+        /// Where to save the shrunk input, `{input}.min.txt` by default.
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Starts an interactive session to explore the parsed input of a day.
+    Repl {
+        /// The day to explore.
+        day: u8,
+        /// Explore this file instead of the cached puzzle input.
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+    },
+    /// Serves the solutions over a local HTTP API.
+    Serve {
+        /// The address to listen on.
+        #[arg(long, default_value = "127.0.0.1:8023")]
+        addr: String,
+        /// Inputs larger than this are rejected.
+        #[arg(long, default_value_t = 1024 * 1024)]
+        max_input_bytes: usize,
+        /// Give up on a solution after this many seconds.
+        #[arg(long, default_value_t = 10)]
+        timeout: u64,
+        /// Turn requests away while this many solutions are running.
+        #[arg(long, default_value_t = 4)]
+        max_in_flight: usize,
+    },
+    /// Watches the files for a day, rerunning its tests and solutions when they change.
+    Watch {
+        /// The day to watch.
+        day: u8,
+        /// How long to wait for changes to settle before rerunning, in milliseconds.
+        #[arg(long, default_value_t = 500)]
+        debounce: u64,
+    },
+}
+
+/// The `implementation` of the solutions to run for `day`, along with the input to run them on.
+fn load(
+    day: u8,
+    part: Option<u8>,
+    input: Option<&PathBuf>,
+    implementation: &str,
+) -> Result<(Vec<(u8, Solver)>, String)> {
+    let solved_day = registry::lookup(day)?;
+    let parts = match part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+    let solvers = parts
+        .into_iter()
+        .filter_map(|part| {
+            solved_day
+                .implementations(part)
+                .into_iter()
+                .find(|(name, _)| *name == implementation)
+                .map(|(_, solver)| (part, solver))
+        })
+        .collect::<Vec<_>>();
+    if solvers.is_empty() {
+        bail!("Day {day} has no implementation named {implementation}");
+    }
+    let input = match input {
+        Some(path) => read_input(path)?,
+        None => try_get_puzzle_input(day as i32, 1)?,
+    };
+    Ok((solvers, input))
+}
+
+/// Where the input of `day` is read from, to point to it in error messages.
+fn input_name(day: u8, input: Option<&PathBuf>) -> String {
+    match input {
+        Some(path) if path == Path::new("-") => "<stdin>".to_string(),
+        Some(path) => path.display().to_string(),
+        None => format!("inputs/day_{day}.txt"),
+    }
+}
+
+/// Parse `input` without solving it, for the days that can, so that a malformed input is
+/// reported with its location rather than as a panic in the middle of a solution.
+fn check_input(day: u8, input: &str, name: &str) -> Result<()> {
+    let Some(check) = registry::lookup(day)?.check else {
+        return Ok(());
+    };
+    check(input).map_err(|e| {
+        match diagnostic::parse_error(&e) {
+            Some(error) => eprint!("{}", diagnostic::render(error, input, name)),
+            None => eprintln!("error: {e:#}"),
+        }
+        anyhow!("Day {day}: malformed input")
+    })
+}
+
+fn run(
+    day: Option<u8>,
+    part: Option<u8>,
+    input: Option<&PathBuf>,
+    mem: bool,
+    timeout: Duration,
+    verbose: u8,
+) -> Result<()> {
+    let days = match day {
+        Some(day) => vec![day],
+        None => registry::DAYS.iter().map(|d| d.day).collect(),
+    };
+    let mut failures = 0;
+    for &day in &days {
+        let name = input_name(day, input);
+        let (solvers, input) = match load(day, part, input, "main") {
+            Ok(loaded) => loaded,
+            // a missing input shouldn't stop the other days
+            Err(e) if days.len() > 1 => {
+                println!("Day {day}: skipped, {e:#}");
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if let Err(e) = check_input(day, &input, &name) {
+            println!("{e}");
+            failures += solvers.len();
+            continue;
+        }
+        for (part, _) in solvers {
+            let mut command = Command::new(std::env::current_exe()?);
+            command.args(["isolated", &day.to_string(), &part.to_string()]);
+            if mem {
+                command.arg("--mem");
+            }
+            if verbose > 0 {
+                command.arg(format!("-{}", "v".repeat(verbose as usize)));
+            }
+            let status = isolate::run_in_subprocess(command, &input, timeout)?;
+            println!("Day {day} part {part}: {status}");
+            if let Status::Solved {
+                allocations: Some(stats),
+                ..
+            } = &status
+            {
+                println!("  {stats}");
+            }
+            if !status.is_solved() {
+                failures += 1;
+            }
+        }
+    }
+    if failures > 0 {
+        bail!("{failures} parts failed");
+    }
+    Ok(())
+}
+
+/// What to do with the timings of a benchmark.
+struct BenchHistory<'a> {
+    path: &'a Path,
+    compare: Option<&'a str>,
+    threshold: f64,
+}
+
+fn bench(
+    day: u8,
+    part: Option<u8>,
+    input: Option<&PathBuf>,
+    iterations: u32,
+    mem: bool,
+    implementation: &str,
+    history: &BenchHistory,
+) -> Result<()> {
+    if iterations == 0 {
+        bail!("Need at least one iteration");
+    }
+    // resolve the revision first, so that a typo doesn't waste a whole benchmark
+    let baseline = history.compare.map(history::resolve).transpose()?;
+    let name = input_name(day, input);
+    let (solvers, input) = load(day, part, input, implementation)?;
+    check_input(day, &input, &name)?;
+    let mut records = vec![];
+    for (part, solver) in solvers {
+        let times = (0..iterations)
+            .map(|_| {
+                let start = Instant::now();
+                // keep the optimizer from skipping work whose answer is thrown away
+                black_box(solver(black_box(&input)));
+                start.elapsed()
+            })
+            .collect::<Vec<_>>();
+        let record = history::Record::new(day, part, implementation, &input, &times);
+        let mean = times.iter().sum::<Duration>() / iterations;
+        let name = match implementation {
+            "main" => String::new(),
+            name => format!(" ({name})"),
+        };
+        println!(
+            "Day {day} part {part}{name}: median {:?}, mean {mean:?}, min {:?}, max {:?} over {iterations} runs",
+            record.median,
+            record.min,
+            times.iter().max().unwrap(),
+        );
+        if mem {
+            // counting slows down allocations, so this gets its own run outside of the timings
+            let (_, stats) = alloc::measure(|| solver(&input));
+            println!("  {stats}");
+        }
+        records.push(record);
+    }
+
+    // loaded before recording this run, so that comparing to the current commit uses the previous runs
+    let past = match baseline {
+        Some(_) => history::load(history.path)?,
+        None => vec![],
+    };
+    match history::current_commit() {
+        Ok((commit, dirty)) => {
+            for record in &mut records {
+                record.commit = commit.clone();
+                record.dirty = dirty;
+            }
+            history::append(history.path, &records)?;
+        }
+        Err(e) => warn!("Not recording the timings, the commit is unknown: {e:#}"),
+    }
+
+    let Some(baseline) = baseline else {
+        return Ok(());
+    };
+    let comparisons = history::compare(&past, &baseline, &records);
+    if comparisons.is_empty() {
+        bail!("No timings recorded for these parts on this input at {baseline}, check it out and run the benchmark there first");
+    }
+    if comparisons.iter().any(|c| c.before.dirty) {
+        warn!("Only runs with uncommitted changes were recorded at {baseline}, they may not time that commit");
+    }
+    println!("Compared to {}:", &baseline[..baseline.len().min(10)]);
+    let threshold = history.threshold / 100.;
+    let mut regressions = 0;
+    for comparison in &comparisons {
+        if comparison.is_regression(threshold) {
+            regressions += 1;
+            println!("  {}", format!("{comparison} REGRESSION").red());
+        } else {
+            println!("  {comparison}");
+        }
+    }
+    if regressions > 0 {
+        bail!(
+            "{regressions} parts got more than {}% slower",
+            history.threshold
+        );
+    }
+    Ok(())
+}
+
+/// Shrink `input` while `part` of `day` keeps failing. The solutions panic a lot on the way,
+/// so their messages are silenced.
+fn shrink_input(day: &registry::Day, part: u8, input: &str, until: Failure) -> Result<String> {
+    let mut fails: Box<dyn FnMut(&str) -> bool> = match until {
+        Failure::Disagree => Box::new(shrink::disagree(day, part)),
+        Failure::Panic => Box::new(shrink::panics(day, part)),
+    };
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let res = if fails(input) {
+        Ok(shrink::shrink(input, day.sections, fails))
+    } else {
+        Err(anyhow!(
+            "Day {} part {part} doesn't fail on this input",
+            day.day
+        ))
+    };
+    panic::set_hook(hook);
+    res
 }
 
-fn main() {
+fn crosscheck_days(
+    day: Option<u8>,
+    options: &crosscheck::Options,
+    out: &Path,
+    shrink: bool,
+) -> Result<()> {
+    let days = match day {
+        Some(day) => {
+            vec![registry::lookup(day)?]
+        }
+        None => registry::DAYS.iter().collect(),
+    };
+    let mut disagreements = 0;
+    for day in days {
+        if day.alternatives.is_empty() {
+            continue;
+        }
+        let found = crosscheck::crosscheck(day, options)?;
+        if found.is_empty() {
+            println!("Day {}: all implementations agree", day.day);
+        }
+        for disagreement in &found {
+            let path = disagreement.save(out)?;
+            println!("{disagreement}\n  input saved to {}", path.display());
+            if shrink {
+                let solved_day = registry::get_day(disagreement.day).unwrap();
+                // an implementation panicking is a disagreement too, which can't be shrunk
+                // without getting the other ones to panic along the way
+                match shrink_input(
+                    solved_day,
+                    disagreement.part,
+                    &disagreement.input,
+                    Failure::Disagree,
+                ) {
+                    Ok(shrunk) => {
+                        let min_path = path.with_extension("min.txt");
+                        std::fs::write(&min_path, shrunk)?;
+                        println!("  shrunk to {}", min_path.display());
+                    }
+                    Err(e) => println!("  not shrunk: {e}"),
+                }
+            }
+        }
+        disagreements += found.len();
+    }
+    if disagreements > 0 {
+        bail!("Found {disagreements} disagreements");
+    }
+    Ok(())
+}
+
+/// Log to stderr, so that the answers on stdout can still be piped around.
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => Level::WARN,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+fn main() -> Result<()> {
     let cli = Cli::parse();
+    init_tracing(cli.verbose);
     // You can check for the existence of subcommands, and if found use their
     // matches just as you would the top level cmd
     match &cli.command {
         Some(Commands::Input { day }) => {
-            let _ = get_puzzle_input(*day as i32, 1);
+            try_get_puzzle_input(*day as i32, 1)?;
+        }
+        Some(Commands::Run {
+            day,
+            part,
+            input,
+            mem,
+            timeout,
+        }) => run(
+            *day,
+            *part,
+            input.as_ref(),
+            *mem,
+            Duration::from_secs(*timeout),
+            cli.verbose,
+        )?,
+        Some(Commands::Isolated { day, part, mem }) => {
+            let solver = registry::get_day(*day)
+                .and_then(|d| d.part(*part))
+                .ok_or_else(|| anyhow!("No solution for day {day} part {part}"))?;
+            let input = read_input(Path::new("-"))?;
+            // the panic is part of the report, no need for the default message too
+            panic::set_hook(Box::new(|_| {}));
+            isolate::report(solver, &input, *mem)?
+        }
+        Some(Commands::Bench {
+            day,
+            part,
+            input,
+            iterations,
+            mem,
+            implementation,
+            compare,
+            threshold,
+            history,
+        }) => {
+            let history = BenchHistory {
+                path: history,
+                compare: compare.as_deref(),
+                threshold: *threshold,
+            };
+            bench(
+                *day,
+                *part,
+                input.as_ref(),
+                *iterations,
+                *mem,
+                implementation,
+                &history,
+            )?
+        }
+        Some(Commands::Generate { day, seed, size }) => {
+            let solved_day = registry::lookup(*day)?;
+            print!("{}", solved_day.generate_input(*seed, *size));
+        }
+        Some(Commands::Crosscheck {
+            day,
+            seeds,
+            size,
+            no_real,
+            out,
+            shrink,
+        }) => {
+            let options = crosscheck::Options {
+                real: !no_real,
+                seeds: *seeds,
+                size: *size,
+                ..Default::default()
+            };
+            crosscheck_days(*day, &options, out, *shrink)?
+        }
+        Some(Commands::Shrink {
+            day,
+            part,
+            input,
+            until,
+            out,
+        }) => {
+            let solved_day = registry::lookup(*day)?;
+            let out = match out {
+                Some(out) => out.clone(),
+                None if input == Path::new("-") => bail!("Need an --out path to shrink stdin"),
+                None => input.with_extension("min.txt"),
+            };
+            let content = read_input(input)?;
+            let shrunk = shrink_input(solved_day, *part, &content, *until)?;
+            std::fs::write(&out, &shrunk)?;
+            println!(
+                "Shrunk from {} to {} bytes, saved to {}",
+                content.len(),
+                shrunk.len(),
+                out.display()
+            );
+        }
+        Some(Commands::Repl { day, input }) => {
+            let input = match input {
+                Some(path) => read_input(path)?,
+                None => try_get_puzzle_input(*day as i32, 1)?,
+            };
+            let mut session = repl::Session::new(*day, input)?;
+            println!("{}", session.describe_input()?);
+            println!("Type `help` to list the available commands");
+            repl::run(&mut session, std::io::stdin().lock(), std::io::stdout())?
+        }
+        Some(Commands::Serve {
+            addr,
+            max_input_bytes,
+            timeout,
+            max_in_flight,
+        }) => {
+            let server = serve::bind(addr)?;
+            println!("Listening on http://{}", server.server_addr());
+            let limits = serve::Limits {
+                max_input_bytes: *max_input_bytes,
+                timeout: Duration::from_secs(*timeout),
+                max_in_flight: *max_in_flight,
+                // in their own process, so that the ones timing out can be killed
+                worker: serve::Worker::Process(std::env::current_exe()?),
+            };
+            serve::serve(server, limits);
+        }
+        Some(Commands::Watch { day, debounce }) => {
+            if !watch::in_repo_root() {
+                bail!("The watch command needs to be run from the root of the repo");
+            }
+            watch::watch(*day, Duration::from_millis(*debounce))?
         }
         None => {}
     }
+    Ok(())
 }
@@ -2,21 +2,25 @@ use std::path::Path;
 
 use reqwest;
 use toml::Table;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use std::io::Read;
 
 
-fn get_cookie() -> String {
+fn get_cookie() -> Result<String> {
     let cookie_file = std::fs::read_to_string("cookie.toml")
-        .expect("Error reading cookie.toml");
+        .context("Error reading cookie.toml, it is needed to download the inputs")?;
     let cookie_table = cookie_file.parse::<Table>()
-        .expect("Error parsing cookie.toml");
-    cookie_table.get("cookie").unwrap().to_string()
+        .context("Error parsing cookie.toml")?;
+    match cookie_table.get("cookie") {
+        Some(cookie) => Ok(cookie.to_string()),
+        None => bail!("No cookie found in cookie.toml"),
+    }
 }
 
 
 /// Get the input for `day` from the advent of code website and cache it as a txt.
 fn get_puzzle_input_from_website(day: i32, part: i32) ->Result<String> {
-    let cookie = format!("session={}", get_cookie());
+    let cookie = format!("session={}", get_cookie()?);
 
     let url = match part {
         1 => format!("https://adventofcode.com/2023/day/{day}/input"),
@@ -28,21 +32,68 @@ fn get_puzzle_input_from_website(day: i32, part: i32) ->Result<String> {
     jar.add_cookie_str(cookie.as_str(), &url);
 
     let client = builder.cookie_provider(jar.into()).build()?;
-    let result = client.get(url).send()?.text()?;
+    // don't cache the error page of a day that is not out yet
+    let result = client.get(url).send()?.error_for_status()?.text()?;
 
-    std::fs::write(format!("inputs/day_{day}.txt").as_str(), result.clone()).unwrap();
+    std::fs::write(format!("inputs/day_{day}.txt").as_str(), result.clone())
+        .context("Error caching the input")?;
     Ok(result)
 }
 
-/// Get the cached file for `day`. If it's not found, get it from the website and cache it.
-pub fn get_puzzle_input(day: i32, part: i32) -> String{
+/// Get the cached file for `day`, if it was already downloaded.
+pub fn cached_puzzle_input(day: i32) -> Result<Option<String>> {
     let path_str = format!("inputs/day_{day}.txt");
     let local_path = Path::new(path_str.as_str());
-    if local_path.is_file() {
-        return std::fs::read_to_string(local_path).expect("Error reading local cached input file");
+    if !local_path.is_file() {
+        return Ok(None);
+    }
+    let input = std::fs::read_to_string(local_path).context("Error reading local cached input file")?;
+    Ok(Some(input))
+}
+
+/// Get the cached file for `day`. If it's not found, get it from the website and cache it.
+pub fn try_get_puzzle_input(day: i32, part: i32) -> Result<String> {
+    if let Some(input) = cached_puzzle_input(day)? {
+        return Ok(input);
+    }
+    get_puzzle_input_from_website(day, part)
+        .with_context(|| format!("Error getting the input for day {day} from the website"))
+}
+
+/// Same as [`try_get_puzzle_input`], panicking if the input can't be found.
+pub fn get_puzzle_input(day: i32, part: i32) -> String{
+    try_get_puzzle_input(day, part).expect("Error getting the puzzle input")
+}
+
+/// Get the real input for `day` in a test, or skip the test if it's not available,
+/// e.g. on a fresh clone without a `cookie.toml`.
+/// Set `AOC_REQUIRE_INPUTS` to make the tests fail instead.
+#[cfg(test)]
+macro_rules! real_input {
+    ($day:expr) => {
+        match $crate::puzzle_inputs::try_get_puzzle_input($day, 1) {
+            Ok(input) => input,
+            Err(e) if std::env::var_os("AOC_REQUIRE_INPUTS").is_none() => {
+                eprintln!("skipped: input not available ({e:#})");
+                return;
+            }
+            Err(e) => panic!("Input for day {} not available: {e:#}", $day),
+        }
     };
-    get_puzzle_input_from_website(day, part).expect("Error reading input file form website")
+}
+#[cfg(test)]
+#[allow(unused_imports)] // only used by the tests of the enabled days
+pub(crate) use real_input;
 
+/// Read an input from an arbitrary file, or from stdin if `path` is `-`.
+pub fn read_input(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        return Ok(input);
+    }
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Error reading input file {}", path.display()))
 }
 
 #[cfg(test)]
@@ -60,18 +111,27 @@ mod tests {
 
     #[test]
     fn test_get_puzzle_input_for_day_1() {
-        let input = get_puzzle_input(1, 1);
-        assert!(input.len() > 0);
+        let input = real_input!(1);
+        assert!(!input.is_empty());
         assert!(!input.contains("Puzzle inputs differ by user."))
     }
 
     #[test]
     fn test_get_puzzle_input_for_days() {
         let curr_day = get_current_day_of_challenge();
-        (1..=curr_day).into_iter().for_each(|d| {
-            let input = get_puzzle_input(d, 1);
-            assert!(input.len() > 0);
+        (1..=curr_day).for_each(|d| {
+            let Ok(input) = try_get_puzzle_input(d, 1) else {
+                eprintln!("skipped day {d}: input not available");
+                return;
+            };
+            assert!(!input.is_empty());
             assert!(!input.contains("Puzzle inputs differ by user."))
         })
     }
+
+    #[test]
+    fn test_missing_input_is_not_cached() {
+        // there is no day 0, so this is never cached
+        assert!(cached_puzzle_input(0).unwrap().is_none());
+    }
 }
@@ -1,6 +1,9 @@
 use once_cell::sync::Lazy;
+use rand::Rng;
 use std::collections::HashMap;
+use tracing::{instrument, trace};
 
+#[instrument(skip_all)]
 pub fn part_1(input: &str) -> i32 {
     let calibration = input
         .trim()
@@ -15,6 +18,7 @@ pub fn part_1(input: &str) -> i32 {
             .parse::<i32>()
             .expect("Error parsing input data.")
         })
+        .inspect(|value| trace!(value, "calibration value"))
         .sum();
 
     calibration
@@ -39,6 +43,7 @@ static DIGIT_NAME_TO_DIGIT_MANGLED: Lazy<HashMap<&str, &str>> = Lazy::new(|| {
 });
 
 /// First solution to part 2. This is a bit yuky because `twone` is expected to give `21` ...
+#[instrument(skip_all)]
 pub fn part_2(input: &str) -> i32 {
     let mut converted_str = input.to_string();
     DIGIT_NAME_TO_DIGIT_MANGLED
@@ -47,11 +52,55 @@ pub fn part_2(input: &str) -> i32 {
     part_1(&converted_str)
 }
 
+/// Generate `size` lines of calibration document, mixing letters, digits and spelled out digits.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    const NAMES: [&str; 9] = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    let mut res = String::new();
+    for _ in 0..size {
+        let mut line = String::new();
+        for _ in 0..rng.gen_range(1..8) {
+            match rng.gen_range(0..3) {
+                0 => line.push(rng.gen_range(b'a'..=b'z') as char),
+                1 => line.push(rng.gen_range(b'1'..=b'9') as char),
+                _ => line += NAMES[rng.gen_range(0..NAMES.len())],
+            }
+        }
+        // part 1 needs at least one actual digit on each line
+        let pos = rng.gen_range(0..=line.len());
+        line.insert(pos, rng.gen_range(b'1'..=b'9') as char);
+        res += &line;
+        res.push('\n');
+    }
+    res
+}
 
 #[cfg(test)]
 mod test_day_1 {
     use super::*;
     use crate::puzzle_inputs;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Straightforward version of part 2, looking for a digit or a digit name at each position.
+    fn calibration_value(line: &str) -> i32 {
+        let names = [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ];
+        let digits = (0..line.len())
+            .filter_map(|i| {
+                let rest = &line[i..];
+                match rest.chars().next().unwrap().to_digit(10) {
+                    Some(d) => Some(d as i32),
+                    None => names
+                        .iter()
+                        .position(|n| rest.starts_with(n))
+                        .map(|p| p as i32 + 1),
+                }
+            })
+            .collect::<Vec<_>>();
+        digits[0] * 10 + digits[digits.len() - 1]
+    }
 
     #[test]
     fn test_part_1_example() {
@@ -62,7 +111,7 @@ mod test_day_1 {
 
     #[test]
     fn test_part_1() {
-        let input = puzzle_inputs::get_puzzle_input(1, 1);
+        let input = puzzle_inputs::real_input!(1);
         let result = part_1(&input);
         k9::assert_equal!(result, 54304);
     }
@@ -84,8 +133,17 @@ zoneight234
 
     #[test]
     fn test_part_2() {
-        let input = puzzle_inputs::get_puzzle_input(1, 1);
+        let input = puzzle_inputs::real_input!(1);
         let result = part_2(&input);
         k9::assert_equal!(result, 54418);
     }
+
+    #[test]
+    fn test_generated_part_2() {
+        for seed in 0..20 {
+            let input = generate(&mut StdRng::seed_from_u64(seed), 50);
+            let expected = input.lines().map(calibration_value).sum::<i32>();
+            k9::assert_equal!(part_2(&input), expected);
+        }
+    }
 }
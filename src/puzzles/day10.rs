@@ -1,6 +1,13 @@
 use colored::*;
+use rand::Rng;
 use std::collections::HashSet;
+use tracing::{debug, instrument, trace, Level};
 
+use crate::utils;
+use crate::utils::geom::{Direction, Point};
+use crate::utils::polygon::Polygon;
+
+#[instrument(name = "parse", skip_all)]
 pub fn parse_input(input: &str) -> Vec<Vec<char>> {
     input
         .lines()
@@ -8,240 +15,210 @@ pub fn parse_input(input: &str) -> Vec<Vec<char>> {
         .collect()
 }
 
-pub fn next_direction(dx: i32, dy: i32, current_char: char) -> (i32, i32) {
-    // println!("getting next_direction: {} {} {}", dx, dy, current_char);
-    if current_char == '|' && dx == 0 {
-        return (0, dy);
-    } else if current_char == '-' && dy == 0 {
-        return (dx, 0);
-    } else if current_char == 'L' && (dx == -1 || dy == 1) {
-        if dx == -1 {
-            return (0, -1);
-        } else {
-            return (1, 0);
-        }
-    } else if current_char == 'J' && (dx == 1 || dy == 1) {
-        if dx == 1 {
-            return (0, -1);
-        } else {
-            return (-1, 0);
-        }
-    } else if current_char == 'F' && (dx == -1 || dy == -1) {
-        if dx == -1 {
-            return (0, 1);
-        } else {
-            return (1, 0);
-        }
-    } else if current_char == '7' && (dx == 1 || dy == -1) {
-        if dx == 1 {
-            return (0, 1);
-        } else {
-            return (-1, 0);
-        }
-    } else {
-        panic!("Invalid char")
+/// The two directions a pipe connects, or `None` if it's not a pipe.
+fn connections(pipe: char) -> Option<[Direction; 2]> {
+    use Direction::*;
+    match pipe {
+        '|' => Some([North, South]),
+        '-' => Some([East, West]),
+        'L' => Some([North, East]),
+        'J' => Some([North, West]),
+        '7' => Some([South, West]),
+        'F' => Some([South, East]),
+        _ => None,
+    }
+}
+
+/// Whether a pipe can be entered going `heading`, i.e. it connects the side we come from.
+fn accepts(pipe: char, heading: Direction) -> bool {
+    connections(pipe).is_some_and(|c| c.contains(&heading.reverse()))
+}
+
+/// The direction to go in after entering `pipe` going `heading`.
+pub fn next_direction(heading: Direction, pipe: char) -> Direction {
+    match connections(pipe) {
+        Some([from, to]) if from == heading.reverse() => to,
+        Some([to, from]) if from == heading.reverse() => to,
+        _ => panic!("Can't enter {pipe} going {heading:?}"),
     }
 }
 
 /// Find the coordinates of the start
-fn find_start(input: &Vec<Vec<char>>) -> (usize, usize) {
-    let mut start_line = 0;
-    let mut start_col = 0;
-    'outer: for (i, line) in input.iter().enumerate() {
-        for (j, c) in line.iter().enumerate() {
+fn find_start(input: &[Vec<char>]) -> Point {
+    for (y, line) in input.iter().enumerate() {
+        for (x, c) in line.iter().enumerate() {
             if *c == 'S' {
-                start_line = i;
-                start_col = j;
-                break 'outer;
+                return Point::new(x as i64, y as i64);
             }
         }
     }
-    (start_line, start_col)
+    panic!("No start in the grid")
 }
 
-/// Find the direction of the first step
-fn find_first_step(
-    start_col: i32,
-    start_line: i32,
-    input: &Vec<Vec<char>>,
-    width: usize,
-    height: usize,
-) -> (char, i32, i32, i32, i32, i32) {
+/// Find the direction of the first step, towards a pipe connected to the start
+fn find_first_step(start: Point, input: &[Vec<char>]) -> Direction {
+    let (width, height) = (input[0].len(), input.len());
     // go around the loop assuming it's a single loop
-    let mut current_char = 'X'; // anything except for `S`
-    let mut curr_distance = 0;
-
-    let mut cur_x = start_col as i32;
-    let mut cur_y = start_line as i32;
-
-    let mut prev_dx = 0;
-    let mut prev_dy = 0;
-
-    for &(dx, dy) in vec![(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
-        let candidate_x = cur_x + dx;
-        let candidate_y = cur_y + dy;
-
-        // continue if this is outside the bounds of the map
-        if candidate_x < 0
-            || candidate_y < 0
-            || candidate_x >= width as i32
-            || candidate_y >= height as i32
-        {
-            continue;
-        }
-
-        let next_char = input[candidate_y as usize][candidate_x as usize];
-
-        if next_char == '|' && dx == 0 {
-        } else if next_char == '-' && dy == 0 {
-        } else if next_char == 'L' && (dx == -1 || dy == 1) {
-        } else if next_char == 'J' && (dx == 1 || dy == 1) {
-        } else if next_char == 'F' && (dx == -1 || dy == -1) {
-        } else if next_char == '7' && (dx == 1 || dy == -1) {
-        } else {
-            continue;
-        }
-        prev_dx = dx;
-        prev_dy = dy;
+    [
+        Direction::East,
+        Direction::West,
+        Direction::South,
+        Direction::North,
+    ]
+    .into_iter()
+    .find(|&heading| {
+        start
+            .step_within(heading, width, height)
+            .is_some_and(|next| accepts(input[next.y as usize][next.x as usize], heading))
+    })
+    .expect("The start is not connected to any pipe")
+}
 
-        cur_x = candidate_x;
-        cur_y = candidate_y;
-        current_char = next_char;
-        curr_distance += 1;
-        break;
+/// The tiles of the loop, in order from the start.
+fn find_loop(input: &[Vec<char>]) -> Vec<Point> {
+    let tile = |p: Point| input[p.y as usize][p.x as usize];
+    let start = find_start(input);
+    let mut heading = find_first_step(start, input);
+    debug!(?heading, "selected first direction");
+
+    // Follow the loop back to the start
+    let mut tiles = vec![start];
+    let mut current = start.step(heading);
+    while tile(current) != 'S' {
+        tiles.push(current);
+        heading = next_direction(heading, tile(current));
+        current = current.step(heading);
     }
-    (current_char, cur_x, cur_y, prev_dx, prev_dy, curr_distance)
+    tiles
 }
 
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     let input = parse_input(input);
-
-    let height = input.len();
-    let width = input[0].len();
-
-    // Find start
-    let (start_line, start_col) = find_start(&input);
-
-    // Find the first step
-    let (mut current_char, mut cur_x, mut cur_y, mut prev_dx, mut prev_dy, mut curr_distance) =
-        find_first_step(start_col as i32, start_line as i32, &input, width, height);
-
-    // Follow the loop counting the length
-    while current_char != 'S' {
-        let (dx, dy) = next_direction(prev_dx, prev_dy, current_char);
-        cur_x = cur_x + dx;
-        cur_y = cur_y + dy;
-        current_char = input[cur_y as usize][cur_x as usize];
-        prev_dx = dx;
-        prev_dy = dy;
-        curr_distance += 1;
-    }
-
-    curr_distance / 2
+    find_loop(&input).len() as i32 / 2
 }
 
+/// The tiles enclosed by the loop are the integer points inside the polygon going through
+/// the middle of its tiles, which Pick's theorem counts from its area.
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i32 {
     let input = parse_input(input);
-
-    let height = input.len();
-    let width = input[0].len();
-    let mut visited = HashSet::<(i32, i32)>::new();
-
-    // Find start
-    let (start_line, start_col) = find_start(&input);
-    visited.insert((start_col as i32, start_line as i32));
-
-    // Find the first step
-    let (mut current_char, mut cur_x, mut cur_y, mut prev_dx, mut prev_dy, _) =
-        find_first_step(start_col as i32, start_line as i32, &input, width, height);
-
-    visited.insert((cur_x, cur_y));
-    let after_start_pos = (cur_x, cur_y);
-    let mut before_start_pos = (0, 0);
-
-    println!("Selected direction {} {}", prev_dx, prev_dy);
-
-    // Follow the loop, this time marking the visited cells
-    while current_char != 'S' {
-        let (dx, dy) = next_direction(prev_dx, prev_dy, current_char);
-        before_start_pos = (cur_x, cur_y);
-        cur_x = cur_x + dx;
-        cur_y = cur_y + dy;
-        current_char = input[cur_y as usize][cur_x as usize];
-        prev_dx = dx;
-        prev_dy = dy;
-        visited.insert((cur_x, cur_y));
+    let tiles = find_loop(&input);
+
+    // the colored grid is only rendered when asking for trace output
+    if tracing::enabled!(Level::TRACE) {
+        let tiles = tiles.iter().collect::<HashSet<_>>();
+        for (y, line) in input.iter().enumerate() {
+            let rendered = line
+                .iter()
+                .enumerate()
+                .map(|(x, &c)| {
+                    if tiles.contains(&Point::new(x as i64, y as i64)) {
+                        c.to_string().blue().to_string()
+                    } else {
+                        c.to_string()
+                    }
+                })
+                .collect::<String>();
+            trace!(row = y, "{rendered}");
+        }
     }
 
-    let mut area = 0;
-
-    // determine what shape the S was
-    println!(
-        "before / after start {},{} -> {},{}",
-        before_start_pos.0, before_start_pos.1, after_start_pos.0, after_start_pos.1
+    let pipes = Polygon::new(tiles);
+    debug!(
+        twice_area = pipes.twice_area(),
+        boundary = pipes.boundary_points(),
+        "closed the loop"
     );
-    let dx = after_start_pos.0 - before_start_pos.0;
-    let dy = after_start_pos.1 - before_start_pos.1;
+    pipes.interior_points() as i32
+}
 
-    println!("dx, dy: {}, {}", dx, dy);
-    let start_char = match (dx, dy) {
-        (0, -2) => '|',
-        (0, 2) => '|',
-        (2, 0) => '-',
-        (-2, 0) => '-',
-        (1, -1) => 'F',
-        (-1, -1) => '7',
-        (1, 1) => 'J',
-        (-1, 1) => 'L',
-        _ => panic!("Invalid start char"),
+/// Generate a grid with a single loop going around a random shape about `size` tiles wide,
+/// with junk pipes around it. Returns the grid, along with the length of the loop and the
+/// number of tiles it encloses.
+pub fn generate_with_answers(rng: &mut impl Rng, size: usize) -> (String, usize, usize) {
+    // the loop goes around a "histogram" of cells, with each column overlapping the previous one,
+    // so that its outline is a single loop without holes or pinches
+    let (width, height) = (rng.gen_range(1..=size.max(1)), size.max(1));
+    let mut columns: Vec<(usize, usize)> = vec![];
+    for _ in 0..width {
+        let (top, bottom) = match columns.last() {
+            None => {
+                let top = rng.gen_range(0..height);
+                (top, rng.gen_range(top..height))
+            }
+            Some(&(prev_top, prev_bottom)) => {
+                let top = rng.gen_range(0..=prev_bottom);
+                (top, rng.gen_range(top.max(prev_top)..height))
+            }
+        };
+        columns.push((top, bottom));
+    }
+    // cells are 2 tiles wide so that the outline has room to turn, and the grid has a margin
+    let in_shape = |x: i64, y: i64| {
+        let (x, y) = (x.div_euclid(2), y.div_euclid(2));
+        x >= 0 && y >= 0 && (x as usize) < width && {
+            let (top, bottom) = columns[x as usize];
+            top <= y as usize && y as usize <= bottom
+        }
     };
-    println!("start_char: {}", start_char);
-
-    // replace it
-    let mut input = input.clone();
-    input[start_line][start_col] = start_char;
-
-    for (i, line) in input.iter().enumerate() {
-        let mut intersections = 0;
-        let mut edge_enter_char = 'X';
-        for (j, &c) in line.iter().enumerate() {
-            if visited.contains(&(j as i32, i as i32)) {
-                if c == '|' {
-                    intersections += 1;
-                    print!("{}", c.to_string().red());
-                } else if c == 'L' || c == 'F' {
-                    edge_enter_char = c;
-                    print!("{}", c.to_string().yellow());
-                } else if c == 'J' || c == '7' {
-                    if (c == 'J' && edge_enter_char == 'F') || (c == '7' && edge_enter_char == 'L')
-                    {
-                        intersections += 1;
-                        print!("{}", c.to_string().red());
-                    } else {
-                        print!("{}", c.to_string().yellow());
-                    }
-                } else {
-                    print!("{}", c.to_string().blue());
-                }
-            } else {
-                if intersections % 2 == 1 {
-                    area += 1;
-                    print!("{}", c.to_string().green());
-                } else {
-                    print!("{}", c.to_string());
-                }
+    // the outline goes from tile (x, y) to its neighbor when exactly one of the squares
+    // on both sides of the segment between them is in the shape
+    let east = |x: i64, y: i64| in_shape(x, y - 1) != in_shape(x, y);
+    let south = |x: i64, y: i64| in_shape(x - 1, y) != in_shape(x, y);
+
+    let (grid_width, grid_height) = (2 * width as i64 + 3, 2 * height as i64 + 3);
+    let mut grid = vec![vec!['.'; grid_width as usize]; grid_height as usize];
+    let mut loop_tiles = vec![];
+    for (row, line) in grid.iter_mut().enumerate() {
+        for (col, tile) in line.iter_mut().enumerate() {
+            let (x, y) = (col as i64 - 1, row as i64 - 1);
+            let connections = (south(x, y - 1), south(x, y), east(x, y), east(x - 1, y));
+            *tile = match connections {
+                (true, true, false, false) => '|',
+                (false, false, true, true) => '-',
+                (true, false, true, false) => 'L',
+                (true, false, false, true) => 'J',
+                (false, true, false, true) => '7',
+                (false, true, true, false) => 'F',
+                _ => ['.', '.', '.', '|', '-', 'L', 'J', '7', 'F'][rng.gen_range(0..9)],
+            };
+            if connections != (false, false, false, false) {
+                loop_tiles.push((row, col));
             }
         }
-        println!("");
     }
 
-    area
+    let (start_row, start_col) = loop_tiles[rng.gen_range(0..loop_tiles.len())];
+    grid[start_row][start_col] = 'S';
+    // only the loop can connect to the start
+    for (row, col) in [
+        (start_row - 1, start_col),
+        (start_row + 1, start_col),
+        (start_row, start_col - 1),
+        (start_row, start_col + 1),
+    ] {
+        if !loop_tiles.contains(&(row, col)) {
+            grid[row][col] = '.';
+        }
+    }
+
+    // Pick's theorem gives the number of enclosed tiles from the area, 4 tiles per cell
+    let area = 4 * columns.iter().map(|(t, b)| b - t + 1).sum::<usize>();
+    let enclosed = area + 1 - loop_tiles.len() / 2;
+    (utils::grid_to_string(&grid), loop_tiles.len(), enclosed)
+}
+
+/// Generate a grid with a single loop, see [`generate_with_answers`].
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    generate_with_answers(rng, size).0
 }
 
 #[cfg(test)]
 mod test_day10 {
-    use super::{part1, part2};
+    use super::{generate_with_answers, next_direction, part1, part2, Direction};
     use crate::puzzle_inputs;
+    use rand::{rngs::StdRng, SeedableRng};
 
     const EXAMPLE_INPUT_PART_1: &str = "\
 -L|F7
@@ -297,7 +274,7 @@ L7JLJL-JLJLJL--JLJ.L";
 
     #[test]
     fn day10_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(10, 1);
+        let input1 = puzzle_inputs::real_input!(10);
         let res = part1(&input1);
         k9::snapshot!(res, "6738");
         k9::assert_equal!(res, 6738);
@@ -326,9 +303,51 @@ L7JLJL-JLJLJL--JLJ.L";
 
     #[test]
     fn day10_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(10, 1);
+        let input2 = puzzle_inputs::real_input!(10);
         let res = part2(&input2);
         k9::snapshot!(res, "579");
         k9::assert_equal!(res, 579);
     }
+
+    #[test]
+    fn day10_next_direction() {
+        use Direction::*;
+        k9::assert_equal!(next_direction(West, 'L'), North);
+        k9::assert_equal!(next_direction(South, 'L'), East);
+        k9::assert_equal!(next_direction(North, '|'), North);
+        k9::assert_equal!(next_direction(East, '7'), South);
+        assert!(std::panic::catch_unwind(|| next_direction(North, '-')).is_err());
+    }
+
+    #[test]
+    fn day10_start_on_l_and_j_corners() {
+        // the shape under the start is worked out from its neighbors, `L` and `J` used to be
+        // swapped, which counted the tiles right of the start on the wrong side of the loop
+        let start_on_l = "\
+.F-7.....
+.|.S--7..
+.|....|..
+.L----J..
+.........";
+        let start_on_j = "\
+....F-7..
+.F--S.|..
+.|....|..
+.L----J..
+.........";
+        for input in [start_on_l, start_on_j] {
+            k9::assert_equal!(part1(input), 8);
+            k9::assert_equal!(part2(input), 5);
+        }
+    }
+
+    #[test]
+    fn day10_generated() {
+        for seed in 0..50 {
+            let (input, loop_length, enclosed) =
+                generate_with_answers(&mut StdRng::seed_from_u64(seed), 10);
+            k9::assert_equal!(part1(&input), loop_length as i32 / 2);
+            k9::assert_equal!(part2(&input), enclosed as i32);
+        }
+    }
 }
@@ -1,3 +1,9 @@
+use itertools::Itertools;
+use rand::Rng;
+use tracing::{debug, instrument, trace};
+
+use crate::utils;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Galaxy {
     row: usize,
@@ -11,6 +17,7 @@ struct Universe {
 /// `dist_mul` controls how much space dialation we want to account for.
 /// for part 1, each empty row or col has 2x
 /// for part 2, each empty row or col has 1_000_000x for the test case and 10 or 100 in the examples
+#[instrument(name = "parse", skip(input))]
 fn parse_input(input: &str, dist_mul: usize) -> Universe {
     let mut galaxies = Vec::new();
     let mut i = 0;
@@ -28,7 +35,7 @@ fn parse_input(input: &str, dist_mul: usize) -> Universe {
             }
         }
         if !has_galaxy {
-            println!("row {} has no galaxies", i);
+            trace!(row = i, "row has no galaxies");
             // if this row had no galaxies, add twice to the counter
             // to account for expansion
             i += dist_mul - 1;
@@ -50,9 +57,12 @@ fn parse_input(input: &str, dist_mul: usize) -> Universe {
             if g.col > prev_col + 1 {
                 // prev galaxy is on a preceding col
                 cuml_cols_to_add += (g.col - (prev_col + 1)) * (dist_mul - 1);
-                println!(
-                    "updating row: {} col: {} prev_col: {} by {}",
-                    g.row, g.col, prev_col, cuml_cols_to_add
+                trace!(
+                    row = g.row,
+                    col = g.col,
+                    prev_col,
+                    cuml_cols_to_add,
+                    "expanding columns"
                 );
                 col = g.col;
             }
@@ -62,6 +72,7 @@ fn parse_input(input: &str, dist_mul: usize) -> Universe {
         })
         .collect();
 
+    debug!(galaxies = galaxies.len(), "parsed universe");
     Universe { galaxies }
 }
 
@@ -93,20 +104,45 @@ fn sum_pairwise_distances(universe: Universe) -> i64 {
     total_dist as i64
 }
 
+/// List the coordinates of the galaxies after expanding the universe by `dist_mul`.
+pub fn expanded_galaxies(input: &str, dist_mul: usize) -> String {
+    parse_input(input, dist_mul)
+        .galaxies
+        .iter()
+        .sorted_by_key(|g| (g.row, g.col))
+        .map(|g| format!("row {} col {}", g.row, g.col))
+        .join("\n")
+}
+
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i64 {
     let universe = parse_input(input, 2);
     sum_pairwise_distances(universe)
 }
 
+#[instrument(skip(input))]
 pub fn part2(input: &str, dist_mul: usize) -> i64 {
     let universe = parse_input(input, dist_mul);
     sum_pairwise_distances(universe)
 }
 
+/// Generate a `size` x `size` image, with some empty rows and columns to expand.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let mut grid = utils::random_grid(rng, size, size, &[('.', 9), ('#', 1)]);
+    for _ in 0..size / 5 {
+        let empty = rng.gen_range(0..size);
+        grid[empty].iter_mut().for_each(|c| *c = '.');
+        let empty = rng.gen_range(0..size);
+        grid.iter_mut().for_each(|row| row[empty] = '.');
+    }
+    utils::grid_to_string(&grid)
+}
+
 #[cfg(test)]
 mod test_day11 {
-    use super::{part1, part2};
+    use super::{expanded_galaxies, generate, part1, part2};
     use crate::puzzle_inputs;
+    use rand::{rngs::StdRng, SeedableRng};
 
     const EXAMPLE_INPUT_PART_1: &str = "\
 ...#......
@@ -132,9 +168,17 @@ mod test_day11 {
         k9::assert_equal!(res, EXAMPLE_OUTPUT_PART_1);
     }
 
+    #[test]
+    fn day11_expanded_galaxies() {
+        let res = expanded_galaxies(EXAMPLE_INPUT_PART_1, 2);
+        k9::assert_equal!(res.lines().count(), 9);
+        k9::assert_equal!(res.lines().next().unwrap(), "row 0 col 4");
+        k9::assert_equal!(res.lines().last().unwrap(), "row 11 col 5");
+    }
+
     #[test]
     fn day11_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(11, 1);
+        let input1 = puzzle_inputs::real_input!(11);
         let res = part1(&input1);
         k9::snapshot!(res, "9795148");
         k9::assert_equal!(res, 9795148);
@@ -156,9 +200,25 @@ mod test_day11 {
 
     #[test]
     fn day11_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(11, 1);
+        let input2 = puzzle_inputs::real_input!(11);
         let res = part2(&input2, 1_000_000);
         k9::snapshot!(res, "650672493820");
         k9::assert_equal!(res, 650672493820);
     }
+
+    #[test]
+    fn day11_generated_distances_are_linear_in_dist_mul() {
+        for seed in 0..20 {
+            let input = generate(&mut StdRng::seed_from_u64(seed), 30);
+            let base = part2(&input, 1);
+            let per_expansion = part2(&input, 2) - base;
+            k9::assert_equal!(part1(&input), base + per_expansion);
+            for dist_mul in [10, 100, 1_000_000] {
+                k9::assert_equal!(
+                    part2(&input, dist_mul),
+                    base + (dist_mul as i64 - 1) * per_expansion
+                );
+            }
+        }
+    }
 }
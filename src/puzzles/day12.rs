@@ -1,21 +1,28 @@
+use anyhow::Result;
 use itertools::Itertools;
 use memoize::memoize;
+use rand::Rng;
+use tracing::{instrument, trace};
+
+use crate::utils::parse::{self, Text};
 
 fn parse_input(input: &str) -> Vec<usize> {
-    input
+    Text::new(input)
         .lines()
         .map(|l| {
-            let (springs, runs) = parse_line(l);
-            get_candidate_count(springs, runs)
+            let (springs, runs) = parse_line(l).unwrap();
+            let count = get_candidate_count(springs, runs);
+            trace!(line = l.as_str(), count, "arrangements");
+            count
         })
         .collect()
 }
 
 fn parse_input_p2(input: &str) -> Vec<usize> {
-    input
+    Text::new(input)
         .lines()
         .map(|l| {
-            let (springs, runs) = parse_line(l);
+            let (springs, runs) = parse_line(l).unwrap();
             let springs = [
                 springs.clone(),
                 springs.clone(),
@@ -25,16 +32,40 @@ fn parse_input_p2(input: &str) -> Vec<usize> {
             ]
             .join("?");
             let runs = (0..5).flat_map(|_| runs.clone()).collect();
-            get_candidate_count(springs, runs)
+            let count = get_candidate_count(springs, runs);
+            trace!(line = l.as_str(), count, "unfolded arrangements");
+            count
         })
         .collect()
 }
 
-fn parse_line(line: &str) -> (String, Vec<usize>) {
-    let (springs, broken) = line.splitn(2, " ").collect_tuple().unwrap();
-    let runs: Vec<usize> = broken.split(',').map(|b| b.parse().unwrap()).collect();
+#[instrument(name = "parse", level = "trace", skip_all)]
+fn parse_line(line: Text) -> parse::Result<(String, Vec<usize>)> {
+    let (springs, broken) = line.split_once(" ")?;
+    if let Some((i, c)) = springs
+        .as_str()
+        .char_indices()
+        .find(|(_, c)| !matches!(c, '.' | '#' | '?'))
+    {
+        return Err(springs
+            .slice(i..i + c.len_utf8())
+            .error(format!("invalid spring `{c}`"))
+            .with_hint("springs are `.` when operational, `#` when damaged and `?` when unknown"));
+    }
+    let runs = broken
+        .split(",")
+        .map(|b| b.parse())
+        .collect::<parse::Result<Vec<usize>>>()?;
     // let runs: Vec<&str> = springs.split(".").filter(|seq| !seq.is_empty()).collect();
-    (springs.to_string(), runs)
+    Ok((springs.as_str().to_string(), runs))
+}
+
+/// Parse the condition records without counting arrangements.
+pub fn check(input: &str) -> Result<()> {
+    for line in Text::new(input).lines() {
+        parse_line(line)?;
+    }
+    Ok(())
 }
 
 #[memoize]
@@ -88,19 +119,73 @@ fn get_candidate_count(springs: String, runs: Vec<usize>) -> usize {
         + get_candidate_count(".".to_string() + &remaining_springs, runs);
 }
 
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> usize {
     // parse_input(input.lines().next().unwrap()).iter().sum()
     parse_input(input).iter().sum()
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> usize {
     parse_input_p2(input).iter().sum()
 }
 
+/// Generate `size` rows of springs, up to `size` springs long, with at least one valid arrangement each.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let mut res = String::new();
+    for _ in 0..size {
+        let len = rng.gen_range(1..=size.max(1));
+        let mut springs = (0..len)
+            .map(|_| if rng.gen_bool(0.5) { '#' } else { '.' })
+            .collect::<Vec<_>>();
+        // each row has at least one damaged spring
+        springs[rng.gen_range(0..len)] = '#';
+        let runs = springs
+            .iter()
+            .collect::<String>()
+            .split('.')
+            .filter(|run| !run.is_empty())
+            .map(|run| run.len())
+            .join(",");
+        // hide some of the springs, the arrangement we started from stays valid
+        let springs = springs
+            .into_iter()
+            .map(|c| if rng.gen_bool(0.5) { '?' } else { c })
+            .collect::<String>();
+        res += &format!("{springs} {runs}\n");
+    }
+    res
+}
+
 #[cfg(test)]
 mod test_day12 {
-    use super::{part1, part2};
+    use super::{check, generate, part1, part2};
     use crate::puzzle_inputs;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Count the arrangements by trying every possible value for the unknown springs.
+    fn brute_force_count(line: &str) -> usize {
+        let (springs, runs) = line.split_once(' ').unwrap();
+        let unknown = springs
+            .match_indices('?')
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        (0..1u32 << unknown.len())
+            .filter(|mask| {
+                let mut candidate = springs.as_bytes().to_vec();
+                for (bit, &i) in unknown.iter().enumerate() {
+                    candidate[i] = if mask & (1 << bit) != 0 { b'#' } else { b'.' };
+                }
+                let candidate_runs = candidate
+                    .split(|&c| c == b'.')
+                    .filter(|run| !run.is_empty())
+                    .map(|run| run.len().to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                candidate_runs == runs
+            })
+            .count()
+    }
 
     const EXAMPLE_INPUT_PART_1: &str = "\
 ???.### 1,1,3
@@ -124,7 +209,7 @@ mod test_day12 {
 
     #[test]
     fn day12_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(12, 1);
+        let input1 = puzzle_inputs::real_input!(12);
         let res = part1(&input1);
         k9::snapshot!(res, "7260");
         k9::assert_equal!(res, 7260);
@@ -139,9 +224,32 @@ mod test_day12 {
 
     #[test]
     fn day12_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(12, 1);
+        let input2 = puzzle_inputs::real_input!(12);
         let res = part2(&input2);
         k9::snapshot!(res, "1909291258644");
         k9::assert_equal!(res, 1909291258644);
     }
+
+    #[test]
+    fn day12_generated_matches_brute_force() {
+        for seed in 0..20 {
+            let input = generate(&mut StdRng::seed_from_u64(seed), 14);
+            for line in input.lines() {
+                let count = part1(line);
+                assert!(count >= 1, "{line} has at least one arrangement");
+                k9::assert_equal!(count, brute_force_count(line), "{line}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = check("???.### 1,1,3\n.??..?x...?##. 1,1,3").unwrap_err();
+        k9::assert_equal!(format!("{err:#}"), "line 2, column 7: invalid spring `x`");
+        let err = check("???.### 1,1,three").unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 1, column 13: invalid value `three`: invalid digit found in string"
+        );
+    }
 }
@@ -1,22 +1,14 @@
 use crate::utils;
-
-#[derive(Debug, Clone)]
-struct Mirrors {
-    rows: Vec<Vec<char>>,
-    cols: Vec<Vec<char>>,
-}
-
-fn parse_inputs(input: &str) -> Vec<Mirrors> {
-    let mut res: Vec<Mirrors> = vec![];
-    for cur_input in input.split("\n\n") {
-        let rows = cur_input
-            .lines()
-            .map(|row| row.chars().collect::<Vec<char>>())
-            .collect::<Vec<Vec<char>>>();
-        let cols = utils::transpose(rows.clone());
-        res.push(Mirrors { rows, cols })
-    }
-    res
+use crate::utils::grid::{Grid, View};
+use rand::Rng;
+use tracing::{debug, instrument};
+
+#[instrument(name = "parse", skip_all)]
+fn parse_inputs(input: &str) -> Vec<Grid<char>> {
+    input
+        .split("\n\n")
+        .map(|pattern| pattern.parse().unwrap())
+        .collect()
 }
 
 enum Direction {
@@ -24,17 +16,22 @@ enum Direction {
     Cols,
 }
 
-fn find_reflections(mirror: Mirrors, direction: Direction) -> Option<usize> {
-    let vector = match direction {
-        Direction::Rows => mirror.rows,
-        Direction::Cols => mirror.cols,
-    };
+/// Look at the pattern so that the lines to compare in `direction` are the rows of the view.
+fn lines(mirror: &Grid<char>, direction: Direction) -> View<'_, char> {
+    match direction {
+        Direction::Rows => mirror.view(),
+        Direction::Cols => mirror.view().transposed(),
+    }
+}
+
+fn find_reflections(mirror: &Grid<char>, direction: Direction) -> Option<usize> {
+    let vector = lines(mirror, direction);
 
     // when considering the i'th row (or col) we check if the mirror line is
     // immediately **after** i but before i + 1
-    for i in 0..vector.len() - 1 {
+    for i in 0..vector.height() - 1 {
         let rows_to_left = i + 1;
-        let rows_to_right = vector.len() - i - 1;
+        let rows_to_right = vector.height() - i - 1;
 
         // this is how many rows need to match
         let cur_max_width = rows_to_left.min(rows_to_right);
@@ -43,7 +40,7 @@ fn find_reflections(mirror: Mirrors, direction: Direction) -> Option<usize> {
         for offset in 0..cur_max_width {
             let left = i - offset;
             let right = i + offset + 1;
-            if vector[left] != vector[right] {
+            if !vector.row(left).eq(vector.row(right)) {
                 break;
             }
             is_mirror = offset == cur_max_width - 1;
@@ -55,17 +52,14 @@ fn find_reflections(mirror: Mirrors, direction: Direction) -> Option<usize> {
     return None;
 }
 
-fn find_reflections_with_smudge(mirror: Mirrors, direction: Direction) -> Option<usize> {
-    let vector = match direction {
-        Direction::Rows => mirror.rows,
-        Direction::Cols => mirror.cols,
-    };
+fn find_reflections_with_smudge(mirror: &Grid<char>, direction: Direction) -> Option<usize> {
+    let vector = lines(mirror, direction);
 
     // when considering the i'th row (or col) we check if the mirror line is
     // immediately **after** i but before i + 1
-    for i in 0..vector.len() - 1 {
+    for i in 0..vector.height() - 1 {
         let rows_to_left = i + 1;
-        let rows_to_right = vector.len() - i - 1;
+        let rows_to_right = vector.height() - i - 1;
 
         // this is how many rows need to match
         let cur_max_width = rows_to_left.min(rows_to_right);
@@ -74,9 +68,9 @@ fn find_reflections_with_smudge(mirror: Mirrors, direction: Direction) -> Option
         for offset in 0..cur_max_width {
             let left = i - offset;
             let right = i + offset + 1;
-            diff_count += vector[left]
-                .iter()
-                .zip(vector[right].iter())
+            diff_count += vector
+                .row(left)
+                .zip(vector.row(right))
                 .map(|(l, r)| if l == r { 0 } else { 1 })
                 .sum::<usize>();
         }
@@ -87,43 +81,89 @@ fn find_reflections_with_smudge(mirror: Mirrors, direction: Direction) -> Option
     return None;
 }
 
+/// Describe each pattern along with where its reflection lines are, with and without a smudge.
+pub fn describe_reflections(input: &str) -> String {
+    parse_inputs(input)
+        .into_iter()
+        .enumerate()
+        .map(|(i, mirror)| {
+            format!(
+                "pattern {i} ({}x{}): rows {:?} cols {:?}, with smudge: rows {:?} cols {:?}",
+                mirror.height(),
+                mirror.width(),
+                find_reflections(&mirror, Direction::Rows),
+                find_reflections(&mirror, Direction::Cols),
+                find_reflections_with_smudge(&mirror, Direction::Rows),
+                find_reflections_with_smudge(&mirror, Direction::Cols),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> usize {
     let all_mirrors = parse_inputs(input);
-    println!("rows");
     let row_reflections: Vec<usize> = all_mirrors
         .iter()
-        .filter_map(|mirror| find_reflections(mirror.clone(), Direction::Rows))
+        .filter_map(|mirror| find_reflections(mirror, Direction::Rows))
         .collect();
 
-    println!("cols");
     let col_reflections: Vec<usize> = all_mirrors
-        .into_iter()
+        .iter()
         .filter_map(|mirror| find_reflections(mirror, Direction::Cols))
         .collect();
 
+    debug!(?row_reflections, ?col_reflections, "found reflections");
     col_reflections.iter().sum::<usize>() + 100 * row_reflections.iter().sum::<usize>()
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> usize {
     let all_mirrors = parse_inputs(input);
-    println!("rows");
     let row_reflections: Vec<usize> = all_mirrors
         .iter()
-        .filter_map(|mirror| find_reflections_with_smudge(mirror.clone(), Direction::Rows))
+        .filter_map(|mirror| find_reflections_with_smudge(mirror, Direction::Rows))
         .collect();
 
-    println!("cols");
     let col_reflections: Vec<usize> = all_mirrors
-        .into_iter()
+        .iter()
         .filter_map(|mirror| find_reflections_with_smudge(mirror, Direction::Cols))
         .collect();
 
+    debug!(?row_reflections, ?col_reflections, "found reflections");
     col_reflections.iter().sum::<usize>() + 100 * row_reflections.iter().sum::<usize>()
 }
 
+/// Generate `size` patterns, each one reflecting along a line once its smudge is fixed.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let (height, width) = (rng.gen_range(2..=15), rng.gen_range(2..=15));
+            let mut rows = utils::random_grid(rng, height, width, &[('.', 1), ('#', 1)]);
+            // reflect the rows before the line onto the ones after it, then add the smudge
+            let line = rng.gen_range(1..height);
+            let reflected = line.min(height - line);
+            for offset in 0..reflected {
+                rows[line + offset] = rows[line - 1 - offset].clone();
+            }
+            let smudged = &mut rows[line + rng.gen_range(0..reflected)];
+            let col = rng.gen_range(0..width);
+            smudged[col] = if smudged[col] == '#' { '.' } else { '#' };
+            let rows = if rng.gen_bool(0.5) {
+                utils::transpose(rows)
+            } else {
+                rows
+            };
+            utils::grid_to_string(&rows)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod test_day13 {
-    use super::{part1, part2};
+    use super::{describe_reflections, part1, part2};
     use crate::puzzle_inputs;
 
     const EXAMPLE_INPUT_PART_1: &str = "\
@@ -154,9 +194,19 @@ mod test_day13 {
         k9::assert_equal!(res, EXAMPLE_OUTPUT_PART_1);
     }
 
+    #[test]
+    fn day13_describe_reflections() {
+        k9::assert_equal!(
+            describe_reflections(EXAMPLE_INPUT_PART_1),
+            "\
+pattern 0 (7x9): rows None cols Some(5), with smudge: rows Some(3) cols None
+pattern 1 (7x9): rows Some(4) cols None, with smudge: rows Some(1) cols None"
+        );
+    }
+
     #[test]
     fn day13_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(13, 1);
+        let input1 = puzzle_inputs::real_input!(13);
         let res = part1(&input1);
         k9::snapshot!(res, "29130");
         k9::assert_equal!(res, 29130);
@@ -171,7 +221,7 @@ mod test_day13 {
 
     #[test]
     fn day13_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(13, 1);
+        let input2 = puzzle_inputs::real_input!(13);
         let res = part2(&input2);
         k9::snapshot!(res, "33438");
         k9::assert_equal!(res, 33438);
@@ -1,143 +1,161 @@
+use anyhow::Result;
 use colored::*;
-use memoize::memoize;
-use std::collections::HashMap;
+use rand::Rng;
+use tracing::{instrument, trace, Level};
 
-use crate::utils::{flip, transpose};
+use crate::utils::bitgrid::BitGrid;
+use crate::utils::parse::{self, Text};
+use crate::utils::{self, grid_to_string, random_grid};
 
 const N_CYCLES: usize = 1_000_000_000;
 
-fn parse_inputs(input: &str) -> Vec<Vec<char>> {
-    input
-        .lines()
-        .map(|row| row.chars().collect::<Vec<char>>())
-        .collect::<Vec<Vec<char>>>()
+/// The rocks on the platform, one bit per tile.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Platform {
+    rounded: BitGrid,
+    cubes: BitGrid,
 }
 
-fn print_input(input: Vec<Vec<char>>) {
-    input.iter().for_each(|r| {
-        r.iter().for_each(|c| {
-            print!(
-                "{}",
-                match c {
-                    'O' => "O".green(),
-                    '#' => "#".red(),
-                    '.' => ".".into(),
-                    _ => panic!(),
-                }
+#[instrument(name = "parse", skip_all)]
+fn parse_inputs(input: &str) -> parse::Result<Platform> {
+    let grid = Text::new(input)
+        .trim()
+        .grid(|c| matches!(c, 'O' | '#' | '.').then_some(c))
+        .map_err(|e| {
+            e.with_hint(
+                "the platform is a rectangle of `O` rounded rocks, `#` cube rocks and `.` empty spaces",
             )
-        });
-        println!();
-    });
-    println!();
+        })?;
+    Ok(Platform {
+        rounded: BitGrid::from_grid(&grid, |c| *c == 'O'),
+        cubes: BitGrid::from_grid(&grid, |c| *c == '#'),
+    })
 }
 
-fn tilt(input: Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let cols = transpose(input);
-    let mut new_cols: Vec<Vec<char>> = vec![]; // for vis only
-
-    for col in cols.iter() {
-        let mut positions: Vec<usize> = Vec::with_capacity(cols[0].len());
-        let mut new_col = Vec::<char>::with_capacity(cols[0].len());
-
-        // smallest row where a rock can roll to
-        let mut last_free_row = 0;
+/// Parse the platform without tilting it.
+pub fn check(input: &str) -> Result<()> {
+    parse_inputs(input)?;
+    Ok(())
+}
 
-        for (i, c) in col.iter().enumerate() {
-            match c {
-                'O' => {
-                    positions.push(last_free_row);
-                    new_col.push(*c);
-                    last_free_row += 1;
-                }
-                '#' => {
-                    positions.push(i);
-                    for _ in new_col.len()..i {
-                        new_col.push('.');
-                    }
-                    new_col.push('#');
-                    last_free_row = i + 1;
-                }
-                '.' => {}
-                _ => panic!("Unrecognized char {c}"),
+/// Render the grid, with the round rocks in green and the cube rocks in red.
+fn format_grid(input: &Platform) -> String {
+    let mut res = String::new();
+    for row in 0..input.rounded.height() {
+        for col in 0..input.rounded.width() {
+            res += &if input.rounded.get((row, col)) {
+                "O".green()
+            } else if input.cubes.get((row, col)) {
+                "#".red()
+            } else {
+                ".".into()
             }
+            .to_string();
         }
-        for _ in new_col.len()..col.len() {
-            new_col.push('.');
-        }
-        new_cols.push(new_col);
+        res.push('\n');
     }
-    transpose(new_cols)
+    res
 }
 
-#[memoize]
-fn cycle(input: Vec<Vec<char>>) -> Vec<Vec<char>> {
-    // println!("INPUT");
-    // print_input(input.clone());
+#[cfg(test)]
+fn print_input(input: Platform) {
+    println!("{}", format_grid(&input));
+}
 
-    // println!("NORTH");
-    let res = tilt(input);
-    // print_input(res.clone());
+/// Log the grid at the trace level, only rendering it when it will actually be shown.
+fn trace_grid(label: &str, input: &Platform) {
+    if tracing::enabled!(Level::TRACE) {
+        trace!("{label}\n{}", format_grid(input));
+    }
+}
 
-    // println!("WEST");
-    let res = transpose(tilt(transpose(res.clone())));
-    // print_input(res.clone());
+impl Platform {
+    /// Roll the rounded rocks north, 64 columns at a time: going down the rows, the rocks
+    /// of each row move up for as long as the tile above them is free.
+    fn tilt_north(&mut self) {
+        for row in 1..self.rounded.height() {
+            for word in 0..self.rounded.row(row).len() {
+                let mut moving = self.rounded.row(row)[word];
+                self.rounded.row_mut(row)[word] = 0;
+                let mut to = row;
+                while to > 0 && moving != 0 {
+                    let free = !(self.rounded.row(to - 1)[word] | self.cubes.row(to - 1)[word]);
+                    // the rocks under something stop here, the others keep rolling
+                    self.rounded.row_mut(to)[word] |= moving & !free;
+                    moving &= free;
+                    to -= 1;
+                }
+                self.rounded.row_mut(to)[word] |= moving;
+            }
+        }
+    }
 
-    // println!("SOUTH");
-    let res = reverse(tilt(reverse(res.clone())));
-    // print_input(res.clone());
+    /// Turn the platform a quarter turn clockwise, bringing its west edge to the north.
+    fn rotate_clockwise(&mut self) {
+        self.rounded.rotate_clockwise();
+        self.cubes.rotate_clockwise();
+    }
 
-    // println!("EAST");
-    let res = flip(transpose(tilt(transpose(flip(res.clone())))));
-    // print_input(res.clone());
-    res
+    /// Each rounded rock weighs as many rows as there are from it to the south edge.
+    fn north_load(&self) -> usize {
+        let height = self.rounded.height();
+        (0..height)
+            .map(|row| self.rounded.count_ones_in_row(row) * (height - row))
+            .sum()
+    }
 }
 
-fn reverse(v: Vec<Vec<char>>) -> Vec<Vec<char>> {
-    v.into_iter().rev().collect()
+/// Tilt the platform north, west, south and east. Rather than turning it to have each edge
+/// north and back, it's tilted north then turned clockwise to bring the next edge north,
+/// which is back where it started after the 4 of them.
+fn cycle(input: Platform) -> Platform {
+    let mut res = input;
+    for label in ["north", "west", "south", "east"] {
+        res.tilt_north();
+        // shown turned, with the edge it was tilted towards at the top
+        trace_grid(label, &res);
+        res.rotate_clockwise();
+    }
+    res
 }
 
-fn get_north_load(input: Vec<Vec<char>>) -> usize {
-    transpose(input)
-        .iter()
-        .flat_map(|col| {
-            col.iter()
-                .enumerate()
-                .filter(|(_, &c)| c == 'O')
-                .map(|(i, _)| col.len() - i)
-        })
-        .sum()
+/// Render the grid after running `n` spin cycles, along with its load on the north beams.
+pub fn grid_after_cycles(input: &str, n: usize) -> String {
+    let mut grid = parse_inputs(input).unwrap();
+    for _ in 0..n {
+        grid = cycle(grid);
+    }
+    format!("{}north load: {}", format_grid(&grid), grid.north_load())
 }
 
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> usize {
-    let input = parse_inputs(input);
-    print_input(input.clone());
-    get_north_load(tilt(input))
+    let mut input = parse_inputs(input).unwrap();
+    trace_grid("input", &input);
+    input.tilt_north();
+    input.north_load()
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> usize {
-    let mut input = parse_inputs(input);
-
-    let mut visited = HashMap::<Vec<Vec<char>>, usize>::new();
-    visited.insert(input.clone(), 0);
-    let mut i = 0;
-
-    while i < N_CYCLES {
-        input = cycle(input);
-        i += 1;
-        if let Some(last_index) = visited.get(&input) {
-            let cycle_length = i - last_index;
-            // println!("found cycle at {last_index} of length: {}", cycle_length);
-            i += ((N_CYCLES - i) / cycle_length) * cycle_length;
-        }
-        visited.insert(input.clone(), i);
-    }
-    get_north_load(input)
+    let input = parse_inputs(input).unwrap();
+    utils::cycle::fast_forward(input, |p| cycle(p.clone()), N_CYCLES).north_load()
+}
+
+/// Generate a `size` x `size` platform with rounded and cube-shaped rocks.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let grid = random_grid(rng, size, size, &[('.', 5), ('O', 2), ('#', 1)]);
+    grid_to_string(&grid)
 }
 
 #[cfg(test)]
 mod test_day14 {
-    use super::{cycle, parse_inputs, part1, part2, print_input};
+    use super::{
+        check, cycle, generate, grid_after_cycles, parse_inputs, part1, part2, print_input,
+    };
     use crate::puzzle_inputs;
+    use crate::utils::bitgrid::BitGrid;
+    use rand::{rngs::StdRng, SeedableRng};
 
     const EXAMPLE_INPUT_PART_1: &str = "\
 O....#....
@@ -164,7 +182,7 @@ O.#..O.#.#
 
     #[test]
     fn day14_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(14, 1);
+        let input1 = puzzle_inputs::real_input!(14);
         let res = part1(&input1);
         k9::snapshot!(res, "109424");
         k9::assert_equal!(res, 109424);
@@ -179,7 +197,7 @@ O.#..O.#.#
 
     #[test]
     fn day14_p2_1_cycle() {
-        let res = cycle(parse_inputs(EXAMPLE_INPUT_PART_2));
+        let res = cycle(parse_inputs(EXAMPLE_INPUT_PART_2).unwrap());
         let expected = "\
 .....#....
 ....#...O#
@@ -193,12 +211,12 @@ O.#..O.#.#
 #..OO#....";
         print_input(res.clone());
         println!("{}", expected);
-        k9::assert_equal!(res, parse_inputs(expected));
+        k9::assert_equal!(res, parse_inputs(expected).unwrap());
     }
 
     #[test]
     fn day14_p2_2_cycle() {
-        let res = cycle(cycle(parse_inputs(EXAMPLE_INPUT_PART_2)));
+        let res = cycle(cycle(parse_inputs(EXAMPLE_INPUT_PART_2).unwrap()));
         let expected = "\
 .....#....
 ....#...O#
@@ -212,12 +230,12 @@ O.#..O.#.#
 #.OOO#...O";
         print_input(res.clone());
         println!("{}", expected);
-        k9::assert_equal!(res, parse_inputs(expected));
+        k9::assert_equal!(res, parse_inputs(expected).unwrap());
     }
 
     #[test]
     fn day14_p2_3_cycle() {
-        let res = cycle(cycle(cycle(parse_inputs(EXAMPLE_INPUT_PART_2))));
+        let res = cycle(cycle(cycle(parse_inputs(EXAMPLE_INPUT_PART_2).unwrap())));
         print_input(res.clone());
         let expected = "\
 .....#....
@@ -231,14 +249,51 @@ O.#..O.#.#
 #...O###.O
 #.OOO#...O";
         println!("{}", expected);
-        k9::assert_equal!(res, parse_inputs(expected));
+        k9::assert_equal!(res, parse_inputs(expected).unwrap());
+    }
+
+    #[test]
+    fn day14_grid_after_cycles() {
+        colored::control::set_override(false);
+        let res = grid_after_cycles(EXAMPLE_INPUT_PART_2, 1);
+        k9::assert_equal!(res.lines().nth(1).unwrap(), "....#...O#");
+        k9::assert_equal!(res.lines().last().unwrap(), "north load: 87");
     }
 
     #[test]
     fn day14_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(14, 1);
+        let input2 = puzzle_inputs::real_input!(14);
         let res = part2(&input2);
         k9::snapshot!(res, "102509");
         k9::assert_equal!(res, 102509);
     }
+
+    #[test]
+    fn day14_generated_cycle_only_moves_rounded_rocks() {
+        let positions = |rocks: &BitGrid| rocks.iter_ones().collect::<Vec<_>>();
+        for seed in 0..20 {
+            let mut grid = parse_inputs(&generate(&mut StdRng::seed_from_u64(seed), 15)).unwrap();
+            let rounded = positions(&grid.rounded).len();
+            let cubes = positions(&grid.cubes);
+            for _ in 0..5 {
+                grid = cycle(grid);
+                k9::assert_equal!(positions(&grid.rounded).len(), rounded);
+                k9::assert_equal!(positions(&grid.cubes), cubes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = check("O.#\n.Z.\n").unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 2, column 2: unexpected `Z` in grid"
+        );
+        let err = check("O.#\n..\n").unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 2, column 1: expected a row of 3 cells, found 2"
+        );
+    }
 }
@@ -1,4 +1,6 @@
 use itertools::Itertools;
+use rand::Rng;
+use tracing::{instrument, trace};
 
 fn hash(prev: i32, c: char) -> i32 {
     ((prev + (c as u8) as i32) * 17) % 256
@@ -7,6 +9,7 @@ fn hash(prev: i32, c: char) -> i32 {
 fn hash_string(s: String) -> i32 {
     s.chars().fold(0, hash)
 }
+#[instrument(name = "parse", skip_all)]
 fn parse_input(input: &str) -> Vec<String> {
     input
         .lines()
@@ -16,14 +19,17 @@ fn parse_input(input: &str) -> Vec<String> {
         .map(|s| s.to_string())
         .collect()
 }
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     parse_input(input).into_iter().map(hash_string).sum()
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> usize {
     let mut res: Vec<Vec<(String, i32)>> = vec![Vec::with_capacity(10); 256];
 
     for step in parse_input(input) {
+        trace!(step, "initialization step");
         if step.ends_with('-') {
             let label = step.trim_end_matches('-');
             let label_hash = hash_string(label.to_string());
@@ -63,6 +69,28 @@ pub fn part2(input: &str) -> usize {
         .sum()
 }
 
+/// Generate an initialization sequence of `size` steps, reusing labels so lenses get replaced and removed.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let labels = (0..size / 4 + 1)
+        .map(|_| {
+            (0..rng.gen_range(2..=6))
+                .map(|_| rng.gen_range(b'a'..=b'z') as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    let steps = (0..size.max(1))
+        .map(|_| {
+            let label = &labels[rng.gen_range(0..labels.len())];
+            if rng.gen_bool(0.3) {
+                format!("{label}-")
+            } else {
+                format!("{label}={}", rng.gen_range(1..=9))
+            }
+        })
+        .join(",");
+    steps + "\n"
+}
+
 #[cfg(test)]
 mod test_day15 {
     use rstest::rstest;
@@ -104,7 +132,7 @@ rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
 
     #[test]
     fn day15_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(15, 1);
+        let input1 = puzzle_inputs::real_input!(15);
         let res = part1(&input1);
         k9::snapshot!(res, "517551");
         k9::assert_equal!(res, 517551);
@@ -119,7 +147,7 @@ rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
 
     #[test]
     fn day15_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(15, 1);
+        let input2 = puzzle_inputs::real_input!(15);
         let res = part2(&input2);
         k9::snapshot!(res, "286097");
         k9::assert_equal!(res, 286097);
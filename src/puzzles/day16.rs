@@ -1,14 +1,13 @@
-use anyhow::{Error, Result};
-use chrono_tz::Australia::{North, South};
+use anyhow::Result;
 use colored::*;
-use indicatif::{ParallelProgressIterator, ProgressIterator};
 use itertools::Itertools;
-use memoize::memoize;
-use num::PrimInt;
-use rayon::prelude::*;
+use rand::Rng;
 use smallvec::{smallvec, SmallVec};
-use std::collections::{HashMap, HashSet};
-use toml::map;
+use tracing::{debug, instrument, trace};
+
+use crate::utils;
+use crate::utils::bitgrid::BitGrid;
+use crate::utils::geom::Direction;
 
 #[derive(Debug)]
 struct Puzzle {
@@ -20,6 +19,36 @@ struct Puzzle {
     bycol: Vec<Vec<Mirror>>,
 }
 
+/// The cells beams went through, separately for each direction they went in.
+struct Visited {
+    beams: [BitGrid; 4],
+}
+
+impl Visited {
+    fn new(puzzle: &Puzzle) -> Visited {
+        Visited {
+            beams: std::array::from_fn(|_| BitGrid::new(puzzle.ncols, puzzle.nrows)),
+        }
+    }
+
+    fn contains(&self, x: usize, y: usize, direction: Direction) -> bool {
+        self.beams[direction as usize].get((y, x))
+    }
+
+    fn insert(&mut self, x: usize, y: usize, direction: Direction) {
+        self.beams[direction as usize].insert((y, x));
+    }
+
+    /// The cells that at least one beam went through, whatever its direction.
+    fn energized(&self) -> BitGrid {
+        let mut res = self.beams[0].clone();
+        for beams in &self.beams[1..] {
+            res |= beams;
+        }
+        res
+    }
+}
+
 impl Puzzle {
     /// starting at position (x, y) and going in the direction or Ray,
     /// find the next intersecting mirror, and record the squares we traversed
@@ -30,17 +59,16 @@ impl Puzzle {
         y: usize,
         direction: Direction,
         first: bool,
-        mut visited: &mut HashSet<(usize, usize, Direction)>,
+        mut visited: &mut Visited,
     ) {
-        // display(input, visited);
-        // println!("Going {direction:?} from ({x}, {y})");
-        if visited.contains(&(x, y, direction)) {
+        trace!(x, y, ?direction, "propagating");
+        if visited.contains(x, y, direction) {
             // we already visited, no need to double count
-            // println!("Already visited {x}, {y} going {direction:?}");
+            trace!(x, y, ?direction, "already visited");
             return;
         }
 
-        visited.insert((x, y, direction));
+        visited.insert(x, y, direction);
 
         match direction {
             Direction::North | Direction::South => {
@@ -56,13 +84,12 @@ impl Puzzle {
                     _ => panic!("Already filtered out other directions"),
                 };
 
-                // println!("next: {next_mirror:?}");
+                trace!(?next_mirror, "next mirror");
                 match next_mirror {
                     Some(mirror) => {
                         // mark the squares as visited
                         for j in range(y, mirror.y) {
-                            // println!("i {x}, {j}, {direction:?}");
-                            visited.insert((x, j, direction));
+                            visited.insert(x, j, direction);
                             // early return if we already visited
                         }
                         // return this as the next mirror
@@ -82,8 +109,7 @@ impl Puzzle {
                     None => {
                         // mark the squares as visited, return None
                         for j in range_inc(y, self.edge(direction)) {
-                            // println!("{x}, {j}, {direction:?}");
-                            visited.insert((x, j, direction));
+                            visited.insert(x, j, direction);
                         }
                     }
                 }
@@ -100,13 +126,12 @@ impl Puzzle {
                         .last(),
                     _ => panic!("Already filtered out other directions"),
                 };
-                // println!("next: {next_mirror:?}");
+                trace!(?next_mirror, "next mirror");
                 match next_mirror {
                     Some(mirror) => {
                         // mark the squares as visited
                         for i in range(x, mirror.x) {
-                            // println!("{i}, {x}, {direction:?}");
-                            visited.insert((i, y, direction));
+                            visited.insert(i, y, direction);
                             // early return if we already visited
                         }
                         // return this as the next mirror
@@ -126,8 +151,7 @@ impl Puzzle {
                     None => {
                         // mark the squares as visited, return None
                         for i in range_inc(x, self.edge(direction)) {
-                            // println!("{i}, {x}, {direction:?}");
-                            visited.insert((i, y, direction));
+                            visited.insert(i, y, direction);
                             // early return if we already visited
                         }
                     }
@@ -166,25 +190,6 @@ fn range(start: usize, end: usize) -> Vec<usize> {
 
 // }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-impl Direction {
-    fn to_offsets(self) -> (i32, i32) {
-        match self {
-            Direction::North => (0, -1),
-            Direction::South => (0, 1),
-            Direction::East => (1, 0),
-            Direction::West => (-1, 0),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 struct Mirror {
     x: usize,
@@ -225,28 +230,56 @@ impl Mirror {
                 _ => panic!("Unrecognized mirror type {}", self.c),
             },
         };
-        // println!("[{}, {}] {:?} => {:?}", self.x, self.y, ray, res);
+        trace!(x = self.x, y = self.y, ?ray, reflected = ?res, "reflected");
         res
     }
 }
 
-fn display(input: &str, visited: &HashSet<(usize, usize, Direction)>) {
+/// Render the grid with the energized cells in red
+fn display(input: &str, visited: &Visited) -> String {
+    let energized = visited.energized();
+    let mut res = String::new();
     for (y, line) in input.lines().enumerate() {
         for (x, c) in line.chars().enumerate() {
-            if visited.contains(&(x, y, Direction::East))
-                || visited.contains(&(x, y, Direction::West))
-                || visited.contains(&(x, y, Direction::North))
-                || visited.contains(&(x, y, Direction::South))
-            {
-                print!("{}", c.to_string().red());
+            if energized.get((y, x)) {
+                res += &c.to_string().red().to_string();
             } else {
-                print!("{}", c)
+                res.push(c);
             }
         }
-        println!();
+        res.push('\n');
+    }
+    res
+}
+
+/// Trace a beam entering from `edge` (one of `north`, `south`, `east` or `west`)
+/// at row or column `index`, and render the cells it energizes.
+pub fn trace_beam(input: &str, edge: &str, index: usize) -> Result<String> {
+    let input = input.trim();
+    let puzzle = parse_input(input);
+    if puzzle.nrows == 0 || puzzle.ncols == 0 {
+        return Err(anyhow::anyhow!("The grid is empty"));
+    }
+    let (x, y, direction) = match edge {
+        "north" => (index, 0, Direction::South),
+        "south" => (index, puzzle.nrows - 1, Direction::North),
+        "east" => (puzzle.ncols - 1, index, Direction::West),
+        "west" => (0, index, Direction::East),
+        _ => return Err(anyhow::anyhow!("Unknown edge {edge}")),
+    };
+    if x >= puzzle.ncols || y >= puzzle.nrows {
+        return Err(anyhow::anyhow!("Index {index} is outside of the grid"));
     }
+    let mut visited = Visited::new(&puzzle);
+    puzzle.propagate(input, x, y, direction, true, &mut visited);
+    let energized = visited.energized().count_ones();
+    Ok(format!(
+        "{}energized: {energized}",
+        display(input, &visited)
+    ))
 }
 
+#[instrument(name = "parse", skip_all)]
 fn parse_input(input: &str) -> Puzzle {
     let n_lines = input.trim().lines().count();
 
@@ -279,6 +312,7 @@ fn parse_input(input: &str) -> Puzzle {
         .into_iter()
         .for_each(|(col, g)| bycol[col] = g.into_iter().collect::<Vec<_>>());
 
+    debug!(nrows = n_lines, ncols = max_line_len, "parsed grid");
     Puzzle {
         nrows: n_lines,
         ncols: max_line_len,
@@ -286,19 +320,17 @@ fn parse_input(input: &str) -> Puzzle {
         byrow,
     }
 }
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> usize {
     let input = input.trim();
     let puzzle = parse_input(input);
-    let mut visited = HashSet::<(usize, usize, Direction)>::new();
+    let mut visited = Visited::new(&puzzle);
     puzzle.propagate(input, 0, 0, Direction::East, true, &mut visited);
 
-    visited
-        .into_iter()
-        .map(|(x, y, _)| (x, y))
-        .collect::<HashSet<(usize, usize)>>()
-        .len()
+    visited.energized().count_ones()
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> usize {
     let input = input.trim();
     let puzzle = parse_input(input);
@@ -308,7 +340,7 @@ pub fn part2(input: &str) -> usize {
     // from NORTH
     let start_y = 0;
     for start_x in 0..puzzle.ncols {
-        let mut visited = HashSet::<(usize, usize, Direction)>::new();
+        let mut visited = Visited::new(&puzzle);
         puzzle.propagate(
             input,
             start_x,
@@ -317,18 +349,14 @@ pub fn part2(input: &str) -> usize {
             true,
             &mut visited,
         );
-        let energized = visited
-            .into_iter()
-            .map(|(x, y, _)| (x, y))
-            .collect::<HashSet<(usize, usize)>>()
-            .len();
+        let energized = visited.energized().count_ones();
         max_energized = max_energized.max(energized);
     }
 
     // from SOUTH
     let start_y = puzzle.nrows - 1;
     for start_x in 0..puzzle.ncols {
-        let mut visited = HashSet::<(usize, usize, Direction)>::new();
+        let mut visited = Visited::new(&puzzle);
         puzzle.propagate(
             input,
             start_x,
@@ -337,46 +365,40 @@ pub fn part2(input: &str) -> usize {
             true,
             &mut visited,
         );
-        let energized = visited
-            .into_iter()
-            .map(|(x, y, _)| (x, y))
-            .collect::<HashSet<(usize, usize)>>()
-            .len();
+        let energized = visited.energized().count_ones();
         max_energized = max_energized.max(energized);
     }
 
     // from EAST
     let start_x = puzzle.ncols - 1;
     for start_y in 0..puzzle.nrows {
-        let mut visited = HashSet::<(usize, usize, Direction)>::new();
+        let mut visited = Visited::new(&puzzle);
         puzzle.propagate(input, start_x, start_y, Direction::West, true, &mut visited);
-        let energized = visited
-            .into_iter()
-            .map(|(x, y, _)| (x, y))
-            .collect::<HashSet<(usize, usize)>>()
-            .len();
+        let energized = visited.energized().count_ones();
         max_energized = max_energized.max(energized);
     }
 
     // from WEST
     let start_x = 0;
     for start_y in 0..puzzle.nrows {
-        let mut visited = HashSet::<(usize, usize, Direction)>::new();
+        let mut visited = Visited::new(&puzzle);
         puzzle.propagate(input, start_x, start_y, Direction::East, true, &mut visited);
-        let energized = visited
-            .into_iter()
-            .map(|(x, y, _)| (x, y))
-            .collect::<HashSet<(usize, usize)>>()
-            .len();
+        let energized = visited.energized().count_ones();
         max_energized = max_energized.max(energized);
     }
 
     max_energized
 }
 
+/// Generate a `size` x `size` contraption with a few mirrors and splitters.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let weights = [('.', 20), ('|', 1), ('-', 1), ('/', 1), ('\\', 1)];
+    utils::grid_to_string(&utils::random_grid(rng, size, size, &weights))
+}
+
 #[cfg(test)]
 mod test_day16 {
-    use super::{parse_input, part1, part2, Direction};
+    use super::{parse_input, part1, part2, trace_beam, Direction, Visited};
     use crate::puzzle_inputs;
     use std::collections::HashSet;
 
@@ -420,7 +442,7 @@ mod test_day16 {
     fn day16_p1_example_energized() {
         let input = EXAMPLE_INPUT_PART_1.trim();
         let puzzle = parse_input(input);
-        let mut visited = HashSet::<(usize, usize, Direction)>::new();
+        let mut visited = Visited::new(&puzzle);
         puzzle.propagate(input, 0, 0, Direction::East, true, &mut visited);
 
         let energized_cells = EXAMPLE_INPUT_PART_1_ENERGIZED
@@ -438,15 +460,26 @@ mod test_day16 {
         k9::assert_equal!(
             energized_cells,
             visited
-                .into_iter()
-                .map(|(x, y, _)| (x, y))
+                .energized()
+                .iter_ones()
+                .map(|(y, x)| (x, y))
                 .collect::<HashSet<(usize, usize)>>()
         );
     }
 
+    #[test]
+    fn day16_trace_beam() {
+        let res = trace_beam(EXAMPLE_INPUT_PART_1, "north", 3).unwrap();
+        k9::assert_equal!(res.lines().last().unwrap(), "energized: 51");
+        assert!(trace_beam(EXAMPLE_INPUT_PART_1, "up", 3).is_err());
+        assert!(trace_beam(EXAMPLE_INPUT_PART_1, "west", 10).is_err());
+        assert!(trace_beam("", "south", 0).is_err());
+        assert!(trace_beam("\n", "east", 0).is_err());
+    }
+
     #[test]
     fn day16_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(16, 1);
+        let input1 = puzzle_inputs::real_input!(16);
         let res = part1(&input1);
         k9::snapshot!(res, "8034");
         k9::assert_equal!(res, 8034);
@@ -461,7 +494,7 @@ mod test_day16 {
 
     #[test]
     fn day16_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(16, 1);
+        let input2 = puzzle_inputs::real_input!(16);
         let res = part2(&input2);
         k9::snapshot!(res, "8225");
         k9::assert_equal!(res, 8225);
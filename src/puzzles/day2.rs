@@ -1,5 +1,8 @@
 use anyhow::{Error, Result};
-use itertools::Itertools;
+use rand::Rng;
+use tracing::{instrument, trace};
+
+use crate::utils::parse::{self, Text};
 
 struct Game {
     id: i32,
@@ -21,56 +24,106 @@ impl Game {
     }
 }
 
-impl std::str::FromStr for Game {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Game> {
-        let (id, rounds) = s.trim().splitn(2, ": ").collect_tuple().unwrap();
-        let id = id.trim().split(' ').nth(1).unwrap().parse::<i32>().unwrap();
+impl Game {
+    /// Parse a line like `Game 1: 3 blue, 4 red; 1 red, 2 green`.
+    fn parse(line: Text) -> parse::Result<Game> {
+        let (id, rounds) = line.trim().split_once(": ")?;
+        let id = id.strip_prefix("Game ")?.parse::<i32>()?;
         let mut red = 0;
         let mut green = 0;
         let mut blue = 0;
 
-        rounds.split("; ").for_each(|r| {
-            r.split(", ").for_each(|c| {
-                let (count, color) = c.splitn(2, ' ').collect_tuple().unwrap();
-                let count = count.trim().parse::<i32>().unwrap();
-                let color = color.trim();
+        for round in rounds.split("; ") {
+            for cubes in round.split(", ") {
+                let (count, color) = cubes.trim().split_once(" ")?;
+                let count = count.parse::<i32>()?;
 
-                match color {
+                match color.as_str().trim() {
                     "red" => red = red.max(count),
                     "blue" => blue = blue.max(count),
                     "green" => green = green.max(count),
-                    _ => panic!("Unknown color {}", color),
+                    other => {
+                        return Err(color
+                            .error(format!("unknown color `{other}`"))
+                            .with_hint("the cubes are red, green or blue"))
+                    }
                 }
-            })
-        });
+            }
+        }
         let game = Self {
             id,
             red,
             green,
             blue,
         };
+        trace!(id, red, green, blue, "parsed game");
         Ok(game)
     }
 }
 
-pub fn part1(inputs: &str) -> i32 {
-    inputs
+impl std::str::FromStr for Game {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Game> {
+        Ok(Game::parse(Text::new(s))?)
+    }
+}
+
+#[instrument(name = "parse", skip_all)]
+fn parse_games(inputs: &str) -> Result<Vec<Game>> {
+    Text::new(inputs)
         .trim()
         .lines()
-        .filter_map(|l| l.parse::<Game>().map(|g| g.id_if_counts_possible()).ok())
+        .map(|line| Ok(Game::parse(line)?))
+        .collect()
+}
+
+/// Parse the games without playing them.
+pub fn check(inputs: &str) -> Result<()> {
+    parse_games(inputs).map(|_| ())
+}
+
+#[instrument(skip_all)]
+pub fn part1(inputs: &str) -> i32 {
+    parse_games(inputs)
+        .unwrap()
+        .iter()
+        .map(Game::id_if_counts_possible)
         .sum()
 }
 
+#[instrument(skip_all)]
 pub fn part2(inputs: &str) -> i32 {
-    inputs
-        .trim()
-        .lines()
-        .filter_map(|l| l.parse::<Game>().map(|g| g.get_power()).ok())
+    parse_games(inputs)
+        .unwrap()
+        .iter()
+        .map(Game::get_power)
         .sum()
 }
 
+/// Generate `size` games, with a few rounds of random cubes each.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let mut res = String::new();
+    for id in 1..=size {
+        let mut rounds = vec![];
+        for _ in 0..rng.gen_range(1..=6) {
+            let mut cubes = vec![];
+            for color in ["red", "green", "blue"] {
+                if rng.gen_bool(0.7) {
+                    cubes.push(format!("{} {color}", rng.gen_range(1..=20)));
+                }
+            }
+            if !cubes.is_empty() {
+                rounds.push(cubes.join(", "));
+            }
+        }
+        if rounds.is_empty() {
+            rounds.push("1 red".to_string());
+        }
+        res += &format!("Game {id}: {}\n", rounds.join("; "));
+    }
+    res
+}
 
 #[cfg(test)]
 mod test_day_2 {
@@ -90,7 +143,7 @@ mod test_day_2 {
 
     #[test]
     fn test_part_1() {
-        let input1 = puzzle_inputs::get_puzzle_input(2, 1);
+        let input1 = puzzle_inputs::real_input!(2);
         k9::assert_equal!(part1(&input1), 2505);
     }
 
@@ -101,8 +154,16 @@ mod test_day_2 {
 
     #[test]
     fn test_part_2() {
-        let input1 = puzzle_inputs::get_puzzle_input(2, 1);
+        let input1 = puzzle_inputs::real_input!(2);
         k9::assert_equal!(part2(&input1), 70265);
     }
 
+    #[test]
+    fn test_parse_error() {
+        let err = parse_games("Game 1: 3 blue\nGame 2: 4 purple").err().unwrap();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 2, column 11: unknown color `purple`"
+        );
+    }
 }
@@ -1,5 +1,9 @@
 use anyhow::{Error, Result};
 use colored::*;
+use rand::Rng;
+use tracing::{debug, instrument};
+
+use crate::utils::parse::Text;
 
 /// Struct representing a number in the schematic
 #[derive(Debug)]
@@ -41,8 +45,14 @@ pub struct Schematic {
 impl std::str::FromStr for Schematic {
     type Err = Error;
 
+    #[instrument(name = "parse", skip_all)]
     fn from_str(s: &str) -> Result<Schematic> {
-        let line_length = s.trim().lines().next().expect("Input has no lines").len();
+        let input = Text::new(s).trim();
+        let first_line = input
+            .lines()
+            .next()
+            .ok_or_else(|| input.error("the schematic is empty"))?;
+        let line_length = first_line.as_str().len();
         let mut last_line: usize = 0;
         let mut numbers: Vec<Vec<Number>> = vec![];
         let mut symbols: Vec<Vec<Symbol>> = vec![];
@@ -51,14 +61,14 @@ impl std::str::FromStr for Schematic {
 
         let mut number_start_idx: usize = 0;
         let mut number_end_idx: usize = 0;
-        let mut prev_line = s.trim().lines().next().unwrap();
+        let mut prev_line = first_line;
 
-        for (row, line) in s.trim().lines().enumerate() {
+        for (row, line) in input.lines().enumerate() {
             if is_parsing_num {
                 numbers[row - 1].push(Number::new(
-                    prev_line[number_start_idx..=number_end_idx]
-                        .parse::<i32>()
-                        .expect("This should be a number"),
+                    prev_line
+                        .slice(number_start_idx..number_end_idx + 1)
+                        .parse::<i32>()?,
                     number_start_idx,
                     number_end_idx,
                 ));
@@ -70,7 +80,7 @@ impl std::str::FromStr for Schematic {
             let mut current_numbers: Vec<Number> = vec![];
             let mut current_symbols: Vec<Symbol> = vec![];
 
-            for (col, cur_char) in line.chars().enumerate() {
+            for (col, cur_char) in line.as_str().chars().enumerate() {
                 if cur_char.is_numeric() {
                     if !is_parsing_num {
                         number_start_idx = col;
@@ -81,9 +91,8 @@ impl std::str::FromStr for Schematic {
                 } else {
                     if is_parsing_num {
                         current_numbers.push(Number::new(
-                            line[number_start_idx..=number_end_idx]
-                                .parse::<i32>()
-                                .expect("This should be a number"),
+                            line.slice(number_start_idx..number_end_idx + 1)
+                                .parse::<i32>()?,
                             number_start_idx,
                             number_end_idx,
                         ))
@@ -107,6 +116,12 @@ impl std::str::FromStr for Schematic {
             numbers,
             symbols,
         };
+        debug!(
+            lines = last_line + 1,
+            numbers = schematic.numbers.iter().map(|n| n.len()).sum::<usize>(),
+            symbols = schematic.symbols.iter().map(|s| s.len()).sum::<usize>(),
+            "parsed schematic"
+        );
         Ok(schematic)
     }
 }
@@ -244,6 +259,12 @@ impl Schematic {
     }
 }
 
+/// Parse the schematic without looking for part numbers.
+pub fn check(input: &str) -> Result<()> {
+    input.parse::<Schematic>().map(|_| ())
+}
+
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     let schematic: Schematic = input.parse().unwrap();
     let result: i32 = schematic
@@ -260,6 +281,7 @@ pub fn part1(input: &str) -> i32 {
     result
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i32 {
     let schematic: Schematic = input.parse().unwrap();
     let result: i32 = schematic
@@ -277,6 +299,32 @@ pub fn part2(input: &str) -> i32 {
     result
 }
 
+/// Generate a `size` x `size` schematic, with numbers and symbols scattered on it.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    const SYMBOLS: &[u8] = b"*#+$/=%@&-";
+    let mut res = String::new();
+    for _ in 0..size {
+        let mut line = String::new();
+        while line.len() < size {
+            let roll = rng.gen_range(0..10);
+            let digits = rng.gen_range(1..=3);
+            if roll < 3 && line.len() + digits <= size && !line.ends_with(|c: char| c.is_numeric())
+            {
+                line += &rng
+                    .gen_range(10u32.pow(digits as u32 - 1)..10u32.pow(digits as u32))
+                    .to_string();
+            } else if roll < 4 {
+                line.push(SYMBOLS[rng.gen_range(0..SYMBOLS.len())] as char);
+            } else {
+                line.push('.');
+            }
+        }
+        res += &line;
+        res.push('\n');
+    }
+    res
+}
+
 #[cfg(test)]
 mod test_day_3 {
     use super::{part1, part2, Schematic};
@@ -319,7 +367,7 @@ mod test_day_3 {
 
     #[test]
     fn test_part_1() {
-        let input1 = puzzle_inputs::get_puzzle_input(3, 1);
+        let input1 = puzzle_inputs::real_input!(3);
         Schematic::print_colored_adjacencies(input1.as_str());
         let res = part1(&input1);
         assert_ne!(res, 548403);
@@ -334,9 +382,18 @@ mod test_day_3 {
 
     #[test]
     fn test_part_2() {
-        let input1 = puzzle_inputs::get_puzzle_input(3, 1);
+        let input1 = puzzle_inputs::real_input!(3);
         let res = part2(&input1);
         Schematic::print_colored_adjacencies_with_gears(input1.as_str());
         k9::assert_equal!(res, 85010461);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err = "\n\n".parse::<Schematic>().unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 1, column 1: the schematic is empty"
+        );
+    }
 }
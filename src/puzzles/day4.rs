@@ -1,5 +1,8 @@
 use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
+use tracing::{instrument, trace, Level};
 
 fn get_line_wins(line: &str) -> usize {
     let (winning, ours) = line.trim().splitn(2, " | ").collect_tuple().unwrap();
@@ -23,9 +26,10 @@ fn get_line_wins(line: &str) -> usize {
     ours.into_iter().filter(|o| winning.contains(&o)).count()
 }
 
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     let mut total_points = 0;
-    for (_i, line) in input.trim().lines().enumerate() {
+    for (i, line) in input.trim().lines().enumerate() {
         let winning_count = get_line_wins(line);
         let cur_total = if winning_count >= 1 {
             2_i32.pow(winning_count as u32 - 1)
@@ -33,11 +37,17 @@ pub fn part1(input: &str) -> i32 {
             0
         };
         total_points += cur_total;
-        // println!("{_i}: {winning_count}, {cur_total}, {total_points}");
+        trace!(
+            card = i + 1,
+            winning_count,
+            points = cur_total,
+            "scored card"
+        );
     }
     total_points
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i32 {
     let mut card_counts = HashMap::<usize, i32>::new();
     let nb_cards = input.trim().lines().count();
@@ -58,15 +68,42 @@ pub fn part2(input: &str) -> i32 {
             );
         }
     }
-    // println!();
-    // card_counts
-    //     .clone()
-    //     .into_iter()
-    //     .sorted_by_key(|(k, _)| *k)
-    //     .for_each(|(k, v)| println!("{k}: {v}"));
+    if tracing::enabled!(Level::TRACE) {
+        card_counts
+            .iter()
+            .sorted_by_key(|(k, _)| **k)
+            .for_each(|(k, v)| trace!(card = k + 1, copies = v, "card count"));
+    }
     card_counts.into_values().sum()
 }
 
+/// Generate `size` scratchcards. Like in the real inputs, cards never win copies past the end of the table.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let mut res = String::new();
+    for id in 1..=size {
+        let numbers = (1..100).collect::<Vec<u32>>();
+        let mut numbers = numbers
+            .choose_multiple(rng, 13)
+            .copied()
+            .collect::<Vec<_>>();
+        let (winning, others) = numbers.split_at_mut(5);
+        // few cards win, otherwise the number of copies grows exponentially with the size
+        let matches = if rng.gen_bool(0.25) {
+            rng.gen_range(1..=3).min(size - id)
+        } else {
+            0
+        };
+        let mut ours = winning[..matches].to_vec();
+        ours.extend(&others[..8 - matches]);
+        ours.shuffle(rng);
+        winning.shuffle(rng);
+
+        let format = |nums: &[u32]| nums.iter().map(|n| format!("{n:>2}")).join(" ");
+        res += &format!("Card {id:>3}: {} | {}\n", format(winning), format(&ours));
+    }
+    res
+}
+
 #[cfg(test)]
 mod test_day_4 {
     use super::{part1, part2};
@@ -93,7 +130,7 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     #[test]
     fn test_part_1() {
-        let input1 = puzzle_inputs::get_puzzle_input(4, 1);
+        let input1 = puzzle_inputs::real_input!(4);
         let res = part1(&input1);
         k9::snapshot!(res, "23235");
         k9::assert_equal!(res, 23235);
@@ -108,7 +145,7 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     #[test]
     fn test_part_2() {
-        let input2 = puzzle_inputs::get_puzzle_input(4, 1);
+        let input2 = puzzle_inputs::real_input!(4);
         let res = part2(&input2);
         k9::snapshot!(res, "5920640");
         k9::assert_equal!(res, 5920640);
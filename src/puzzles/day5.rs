@@ -1,7 +1,23 @@
 use anyhow::{Error, Result};
-use indicatif::ParallelProgressIterator;
+use indicatif::{ParallelProgressIterator, ProgressBar};
 use itertools::Itertools;
+use rand::Rng;
 use rayon::prelude::*;
+use tracing::{debug, instrument, trace, Level};
+
+use crate::utils::intervals::{IntervalSet, Rule};
+use crate::utils::parse::{self, Text};
+
+/// The maps of the almanac, in the order they are applied.
+const MAP_NAMES: [&str; 7] = [
+    "seed-to-soil",
+    "soil-to-fertilizer",
+    "fertilizer-to-water",
+    "water-to-light",
+    "light-to-temperature",
+    "temperature-to-humidity",
+    "humidity-to-location",
+];
 
 struct PuzzleInput {
     seeds: Vec<i64>,
@@ -17,34 +33,29 @@ struct PuzzleInput {
 impl std::str::FromStr for PuzzleInput {
     type Err = Error;
 
+    #[instrument(name = "parse", skip_all)]
     fn from_str(s: &str) -> Result<PuzzleInput> {
-        let mut lines = s.trim().lines();
-        let seeds = lines
+        let input = Text::new(s);
+        let mut sections = input.sections();
+        let seeds = sections
             .next()
-            .unwrap()
-            .split_whitespace()
-            .skip(1)
-            .map(|s| s.parse::<i64>().unwrap())
-            .collect::<Vec<_>>();
-        let _ = lines.next();
-
-        // println!("Getting seed to soil map");
-        let seed_to_soil_map = get_map_for_current_lines(&mut lines);
-        // println!("Getting soil to fertilizer map");
-        let soil_to_fertilizer_map = get_map_for_current_lines(&mut lines);
-        // println!("Getting fertilizer to water map");
-        let fertilizer_to_water_map = get_map_for_current_lines(&mut lines);
-        // println!("Getting water to light map");
-        let water_to_light_map = get_map_for_current_lines(&mut lines);
-        // println!("Getting light to temperature map");
-        let light_to_temperature_map = get_map_for_current_lines(&mut lines);
-        // println!("Getting temperature to humidity map");
-        let temperature_to_humidity_map = get_map_for_current_lines(&mut lines);
-        // println!("Getting humidity to location map");
-        let humidity_to_location_map = get_map_for_current_lines(&mut lines);
-        // println!("Done parsing input");
-
-        // println!("seed_to_soil_map: {:?}", seed_to_soil_map);
+            .ok_or_else(|| input.error("the almanac is empty"))?
+            .field("seeds")?
+            .integers::<i64>()?;
+
+        let mut next_map = |name: &str| match sections.next() {
+            Some(section) => parse_map(section, name),
+            None => Err(input.end().error(format!("missing the {name} map"))),
+        };
+        let seed_to_soil_map = next_map(MAP_NAMES[0])?;
+        let soil_to_fertilizer_map = next_map(MAP_NAMES[1])?;
+        let fertilizer_to_water_map = next_map(MAP_NAMES[2])?;
+        let water_to_light_map = next_map(MAP_NAMES[3])?;
+        let light_to_temperature_map = next_map(MAP_NAMES[4])?;
+        let temperature_to_humidity_map = next_map(MAP_NAMES[5])?;
+        let humidity_to_location_map = next_map(MAP_NAMES[6])?;
+        debug!(seeds = seeds.len(), "parsed almanac");
+
         // assert_eq!(50, seed_to_soil_map.get(98));
         // assert_eq!(51, seed_to_soil_map.get(99));
         // assert_eq!(55, seed_to_soil_map.get(53));
@@ -64,9 +75,7 @@ impl std::str::FromStr for PuzzleInput {
 
 impl PuzzleInput {
     pub fn get_location(&self, seed: i64) -> i64 {
-        // println!("seed: {}", seed);
         let soil = self.seed_to_soil_map.get(seed);
-        // println!("soil: {}", soil);
         let fertilizer = self.soil_to_fertilizer_map.get(soil);
         let water = self.fertilizer_to_water_map.get(fertilizer);
         let light = self.water_to_light_map.get(water);
@@ -74,13 +83,33 @@ impl PuzzleInput {
         let humidity = self.temperature_to_humidity_map.get(temperature);
         let location = self.humidity_to_location_map.get(humidity);
 
-        // println!(
-        //     "seed: {}, soil: {}, fertilizer: {}, water: {}, light: {}, temperature: {}, humidity: {}, location: {}",
-        //     seed, soil, fertilizer, water, light, temperature, humidity, location
-        // );
+        trace!(
+            seed,
+            soil,
+            fertilizer,
+            water,
+            light,
+            temperature,
+            humidity,
+            location
+        );
         location
     }
 
+    /// All the maps, in the order they are applied, along with their name in the input.
+    fn named_maps(&self) -> [(&'static str, &SmartMap); 7] {
+        let maps = [
+            &self.seed_to_soil_map,
+            &self.soil_to_fertilizer_map,
+            &self.fertilizer_to_water_map,
+            &self.water_to_light_map,
+            &self.light_to_temperature_map,
+            &self.temperature_to_humidity_map,
+            &self.humidity_to_location_map,
+        ];
+        std::array::from_fn(|i| (MAP_NAMES[i], maps[i]))
+    }
+
     pub fn get_closest_location(&self) -> i64 {
         self.seeds
             .iter()
@@ -90,8 +119,15 @@ impl PuzzleInput {
     }
 
     pub fn get_closest_pairwise_location(&self) -> i64 {
+        let nb_ranges = self.seeds.len() / 2;
+        // the progress bar is only shown when asking for debug output
+        let progress = if tracing::enabled!(Level::DEBUG) {
+            ProgressBar::new(nb_ranges as u64)
+        } else {
+            ProgressBar::hidden()
+        };
         // iterate over seeds 2 by 2
-        (0..(self.seeds.len() / 2))
+        (0..nb_ranges)
             .into_par_iter()
             .map(|i| {
                 let mut locations: Vec<i64> = vec![];
@@ -101,10 +137,10 @@ impl PuzzleInput {
                     let loc = self.get_location(j);
                     locations.push(loc);
                 }
-                println!("{i}/{}", self.seeds.len() / 2);
+                debug!(range = i, nb_ranges, "done with seed range");
                 locations.iter().min().unwrap().clone()
             })
-            .progress()
+            .progress_with(progress)
             .min()
             .unwrap()
     }
@@ -140,6 +176,13 @@ impl SmartMap {
         self.len.extend(other.len);
     }
 
+    /// The map as interval rules, in the same order so that the first matching one wins.
+    pub fn rules(&self) -> Vec<Rule> {
+        (0..self.src.len())
+            .map(|i| Rule::new(self.dest[i], self.src[i], self.len[i]))
+            .collect()
+    }
+
     pub fn get(&self, src: i64) -> i64 {
         for i in 0..self.src.len() {
             if self.src[i] <= src && src < self.src[i] + self.len[i] {
@@ -150,156 +193,135 @@ impl SmartMap {
     }
 }
 
-fn get_map_for_current_lines(lines: &mut std::str::Lines) -> SmartMap {
-    let lines = lines.skip(1);
+/// Parse a section of the almanac, with the map called `name`.
+fn parse_map(section: Text, name: &str) -> parse::Result<SmartMap> {
+    let mut lines = section.lines();
+    // sections are never empty
+    let header = lines.next().unwrap();
+    if header.as_str().trim() != format!("{name} map:") {
+        return Err(header.error(format!("expected `{name} map:`")));
+    }
     let mut map = SmartMap::default();
-    lines
-        .take_while(|line| !line.is_empty())
-        .map(|line| line_to_map(line))
-        .for_each(|m| map.extend(m));
-    map
+    for line in lines {
+        let [dest, src, len] = line.integers::<i64>()?[..] else {
+            return Err(line
+                .error("expected a destination, a source and a length")
+                .with_hint("each line of a map is `<destination> <source> <length>`"));
+        };
+        map.extend(SmartMap::new(src, dest, len));
+    }
+    Ok(map)
+}
+
+/// Describe the parsed almanac: the seeds and the size of each map.
+pub fn summary(input: &str) -> Result<String> {
+    let puzzle_input = input.parse::<PuzzleInput>()?;
+    let mut res = format!(
+        "{} seeds: {:?}",
+        puzzle_input.seeds.len(),
+        puzzle_input.seeds
+    );
+    for (name, map) in puzzle_input.named_maps() {
+        res += &format!("\n{name}: {} ranges", map.src.len());
+    }
+    Ok(res)
 }
 
-fn line_to_map(line: &str) -> SmartMap {
-    // println!("{}", line);
-    let (dest, src, len) = line
-        .split_whitespace()
-        .map(|s| s.parse::<i64>().unwrap())
-        .collect_tuple()
-        .unwrap();
-    SmartMap::new(src, dest, len)
+/// Look up `value` in the map called `map_name`, e.g. `seed-to-soil`.
+pub fn map_value(input: &str, map_name: &str, value: i64) -> Result<i64> {
+    let puzzle_input = input.parse::<PuzzleInput>()?;
+    let (_, map) = puzzle_input
+        .named_maps()
+        .into_iter()
+        .find(|(name, _)| *name == map_name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown map {map_name}"))?;
+    Ok(map.get(value))
 }
 
+/// Follow `seed` through all of the maps, showing each intermediate value.
+pub fn trace_seed(input: &str, seed: i64) -> Result<String> {
+    let puzzle_input = input.parse::<PuzzleInput>()?;
+    let mut value = seed;
+    let mut res = format!("seed {seed}");
+    for (name, map) in puzzle_input.named_maps() {
+        value = map.get(value);
+        let (_, dest) = name.split_once("-to-").unwrap();
+        res += &format!(" -> {dest} {value}");
+    }
+    Ok(res)
+}
+
+/// Parse the almanac without following any seed.
+pub fn check(input: &str) -> Result<()> {
+    input.parse::<PuzzleInput>().map(|_| ())
+}
+
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i64 {
     let puzzle_input = input.parse::<PuzzleInput>().unwrap();
     puzzle_input.get_closest_location()
 }
 
 /// Takes less than a minute with parallelization and release compilation mode
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i64 {
     let puzzle_input = input.parse::<PuzzleInput>().unwrap();
     puzzle_input.get_closest_pairwise_location()
 }
 
-/// basically instant
-pub fn part2_take2(input: &str) -> usize {
-    let (seeds, maps) = input.split_once("\n\n").unwrap();
-    let seeds = seeds
-        .split_ascii_whitespace()
-        .filter_map(|id| id.parse::<usize>().ok())
-        .collect::<Vec<_>>();
+/// basically instant: follows the seed ranges through the maps, splitting them
+/// where the map ranges start and end, instead of each seed one by one
+#[instrument(skip_all)]
+pub fn part2_take2(input: &str) -> i64 {
+    let puzzle_input = input.parse::<PuzzleInput>().unwrap();
+    let mut values = puzzle_input
+        .seeds
+        .chunks_exact(2)
+        .map(|pair| pair[0]..pair[0] + pair[1])
+        .collect::<IntervalSet>();
 
-    let maps = maps
-        .split("\n\n")
-        .map(|m| {
-            m.lines()
-                .skip(1)
-                .map(|l| {
-                    l.split_ascii_whitespace()
-                        .filter_map(|num| num.parse::<usize>().ok())
-                        .collect::<Vec<usize>>()
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
+    // seed -> soil then soil -> fertilizer -> ...-> water -> light -> temperature -> humidity -> location
+    for (name, map) in puzzle_input.named_maps() {
+        values = values.map(&map.rules());
+        trace!(map = name, %values, "translated ranges");
+    }
+    values.min().unwrap()
+}
 
-    // translate each seed interval into a range
-    // This array will hold the range of seeds at the start, and we'll
-    // repeatedly translate it until we get to the end of the maps
-    let mut arr = seeds
-        .chunks_exact(2)
-        .map(|ele| ele[0]..(ele[0] + ele[1]))
+/// Pick `n` disjoint ranges in `0..domain`, as `(start, len)` sorted by start.
+fn random_disjoint_ranges(rng: &mut impl Rng, n: usize, domain: i64) -> Vec<(i64, i64)> {
+    let mut bounds = rand::seq::index::sample(rng, domain as usize, 2 * n)
+        .into_iter()
+        .map(|b| b as i64)
         .collect::<Vec<_>>();
+    bounds.sort_unstable();
+    bounds
+        .chunks_exact(2)
+        .map(|b| (b[0], b[1] - b[0]))
+        .collect()
+}
 
-    // iterate over each layer of the mappings
-    // seed -> soil then soil -> fertilizer -> ...-> water -> light -> temperature -> humidity -> location
-    for mut map in maps {
-        // sort by source
-        map.sort_unstable_by(|a, b| a[1].cmp(&b[1]));
-
-        // 1. First, we check if the current range is solved. If it is, we move to the next range.
-        // 2. We iterate over the list of mappings.
-        //     For each mapping, we check if the current range is fully contained in the mapping.
-        //     - If it is, we translate the current range and mark it as solved.
-        //     If it is not, we check if the current range is partially contained in the mapping.
-        //     - If it is, we split the current range into two ranges.
-        //     - We translate the first range and mark it as solved.
-        //     - We add the second range to the list of ranges to be solved.
-        // 3. Once we have translated all the ranges in the list of ranges, we return the minimum value of the start of the ranges.
-
-        // While our array of inputs is not fully translated
-        let mut idx = 0;
-        while arr.get(idx).is_some() {
-            // This is the current range we want to translate.
-            let input_range = arr[idx].clone();
-
-            // iterate over each range in the current layer of mappings
-            // until we find a mapping that contains the current range
-            // even if partially. Then we plit the current range into two ranges
-            // if needed, apply that mapping to the overlapping part
-            // and add the remaining part if any to the array of ranges to be translated
-            // we break because we have fully translated the current range
-            for m in map.iter() {
-                let destination = m[0];
-                let source = m[1];
-                let length = m[2];
-                let range = source..(source + length);
-
-                let input_start = input_range.start;
-                let input_end = input_range.end - 1;
-
-                let start_distance = input_start.saturating_sub(source);
-                let end_distance = input_end.saturating_sub(source);
-
-                if range.contains(&input_start) && range.contains(&input_end) {
-                    // range fully contains current range
-                    // we replace it in the array with the translated range
-                    arr[idx] = (destination + start_distance)..(destination + end_distance);
-                } else if range.contains(&input_start) && !range.contains(&input_end) {
-                    // range contains start of current range, but does not contain the end
-                    // we replace the overlapping part
-                    arr[idx] = (destination + start_distance)..(destination + length);
-
-                    // create a new range with the rest
-                    let next_range = (source + length)..input_end + 1;
-
-                    // println!(
-                    //     "Start Included, End Excluded -> Split Range: {:?} -> {:?}",
-                    //     current_start..source + length,
-                    //     next_range
-                    // );
-                    // println!("Output Range -> {:?}", arr[idx]);
-
-                    // push the new range to the array
-                    arr.insert(idx + 1, next_range);
-                } else if !range.contains(&input_start) && range.contains(&input_end) {
-                    // translate the current overlap
-                    arr[idx] = (destination)..(destination + end_distance);
-                    // create a new range with the rest
-                    let next_range = (input_start)..(source);
-
-                    // println!(
-                    //     "Start Excluded, End Included -> Split Range: {:?} -> {:?}",
-                    //     source..source + end_distance,
-                    //     next_range
-                    // );
-                    // println!("Output Range -> {:?}", arr[idx]);
-
-                    arr.insert(idx + 1, next_range);
-                }
-                // There is no default else case here, becase in that case the translation is 1:1 so
-                // we don't need to do anything to the range, just keep it and move on
-            }
-            idx += 1;
+/// Generate an almanac with `size` seed ranges, and up to `size` ranges in each map.
+/// The seed ranges are kept short so that part 2 can still be brute forced.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let domain = 1000 * size.max(1) as i64;
+    let seeds = (0..size.max(1))
+        .flat_map(|_| [rng.gen_range(0..domain), rng.gen_range(1..=100)])
+        .join(" ");
+    let mut res = format!("seeds: {seeds}\n");
+    for name in MAP_NAMES {
+        res += &format!("\n{name} map:\n");
+        let n = rng.gen_range(1..=size.max(1));
+        for (src, len) in random_disjoint_ranges(rng, n, domain) {
+            res += &format!("{} {src} {len}\n", rng.gen_range(0..domain));
         }
     }
-
-    arr.iter().map(|r| r.start).min().unwrap()
+    res
 }
 
 #[cfg(test)]
 mod test_day_5 {
-    use super::{part1, part2_take2};
+    use super::{map_value, part1, part2_take2, trace_seed, PuzzleInput, MAP_NAMES};
     use crate::puzzle_inputs;
 
     /// Here 114 and 58 are not adjacent to anything
@@ -348,9 +370,26 @@ humidity-to-location map:
         k9::assert_equal!(part1(EXAMPLE_INPUT_PART_1), EXAMPLE_OUTPUT_PART_1);
     }
 
+    #[test]
+    fn day5_inspect_example() {
+        k9::assert_equal!(
+            map_value(EXAMPLE_INPUT_PART_1, "seed-to-soil", 79).unwrap(),
+            81
+        );
+        k9::assert_equal!(
+            map_value(EXAMPLE_INPUT_PART_1, "seed-to-soil", 10).unwrap(),
+            10
+        );
+        assert!(map_value(EXAMPLE_INPUT_PART_1, "seed-to-nowhere", 10).is_err());
+        k9::assert_equal!(
+            trace_seed(EXAMPLE_INPUT_PART_1, 79).unwrap(),
+            "seed 79 -> soil 81 -> fertilizer 81 -> water 81 -> light 74 -> temperature 78 -> humidity 78 -> location 82"
+        );
+    }
+
     #[test]
     fn day5_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(5, 1);
+        let input1 = puzzle_inputs::real_input!(5);
         println!("got input");
         let res = part1(&input1);
         k9::snapshot!(res, "174137457");
@@ -359,16 +398,57 @@ humidity-to-location map:
 
     #[test]
     fn day5_p2_example() {
-        let res = part2_take2(EXAMPLE_INPUT_PART_2) as i64;
+        let res = part2_take2(EXAMPLE_INPUT_PART_2);
         k9::snapshot!(res, "46");
         k9::assert_equal!(res, EXAMPLE_OUTPUT_PART_2);
     }
 
+    #[test]
+    fn day5_p2_ranges_split_and_mapped_once() {
+        let almanac = |seed_to_soil: &str| {
+            let maps = MAP_NAMES
+                .iter()
+                .map(|name| format!("{name} map:\n"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            maps.replacen(
+                "seed-to-soil map:\n",
+                &format!("seed-to-soil map:\n{seed_to_soil}\n"),
+                1,
+            )
+        };
+        // a map range strictly inside the seed range splits it in three
+        let input = format!("seeds: 5 10\n\n{}", almanac("0 10 2"));
+        k9::assert_equal!(part2_take2(&input), 0);
+        // the soil 20 to 22 the seeds are mapped to isn't mapped again to 30 by the same map
+        let input = format!("seeds: 10 3\n\n{}", almanac("20 10 3\n30 20 3"));
+        k9::assert_equal!(part2_take2(&input), 20);
+    }
+
     #[test]
     fn day5_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(5, 1);
-        let res = part2_take2(&input2) as i64;
+        let input2 = puzzle_inputs::real_input!(5);
+        let res = part2_take2(&input2);
         k9::snapshot!(res, "1493866");
         k9::assert_equal!(res, 1493866);
     }
+
+    #[test]
+    fn day5_parse_errors() {
+        let error = |input: &str| format!("{:#}", input.parse::<PuzzleInput>().err().unwrap());
+        let (seeds, maps) = EXAMPLE_INPUT_PART_1.split_once("\n\n").unwrap();
+        k9::assert_equal!(
+            error(&format!("{seeds}\n\n{}", maps.replace("52 50 48", "52 50"))),
+            "line 5, column 1: expected a destination, a source and a length"
+        );
+        k9::assert_equal!(
+            error(maps),
+            "line 1, column 1: expected `seeds:`, found `seed-to-soil map:`"
+        );
+        let (start, _) = EXAMPLE_INPUT_PART_1.split_once("\n\nhumidity").unwrap();
+        k9::assert_equal!(
+            error(start),
+            "line 29, column 7: missing the humidity-to-location map"
+        );
+    }
 }
@@ -1,4 +1,8 @@
 use anyhow::{Error, Result};
+use rand::Rng;
+use tracing::{debug, instrument, trace};
+
+use crate::utils::parse::{self, Text};
 
 #[derive(Debug)]
 struct PuzzleInput {
@@ -9,22 +13,20 @@ struct PuzzleInput {
 impl std::str::FromStr for PuzzleInput {
     type Err = Error;
 
+    #[instrument(name = "parse", skip_all)]
     fn from_str(s: &str) -> Result<PuzzleInput> {
-        let mut lines = s.trim().lines();
-        let times = lines
-            .next()
-            .unwrap()
-            .split_whitespace()
-            .skip(1)
-            .map(|s| s.parse::<i64>().unwrap())
-            .collect::<Vec<_>>();
-        let distances = lines
-            .next()
-            .unwrap()
-            .split_whitespace()
-            .skip(1)
-            .map(|s| s.parse::<i64>().unwrap())
-            .collect::<Vec<_>>();
+        let (time_line, distance_line) = two_lines(s)?;
+        let times = time_line.field("Time")?.integers::<i64>()?;
+        let distances = distance_line.field("Distance")?.integers::<i64>()?;
+        if times.len() != distances.len() {
+            return Err(distance_line
+                .error(format!(
+                    "expected {} distances, one for each time, found {}",
+                    times.len(),
+                    distances.len()
+                ))
+                .into());
+        }
         Ok(PuzzleInput { times, distances })
     }
 }
@@ -38,37 +40,53 @@ struct PuzzleInputPart2 {
 impl std::str::FromStr for PuzzleInputPart2 {
     type Err = Error;
 
+    #[instrument(name = "parse", skip_all)]
     fn from_str(s: &str) -> Result<PuzzleInputPart2> {
-        let mut lines = s.trim().lines();
-        let time = lines
-            .next()
-            .unwrap()
-            .split(':')
-            .skip(1)
-            .collect::<String>()
-            .replace(" ", "")
-            .parse::<i64>()
-            .unwrap();
-        let distance = lines
-            .next()
-            .unwrap()
-            .split(':')
-            .skip(1)
-            .collect::<String>()
-            .replace(" ", "")
-            .parse::<i64>()
-            .unwrap();
+        let (time_line, distance_line) = two_lines(s)?;
+        let time = kerned_number(time_line.field("Time")?)?;
+        let distance = kerned_number(distance_line.field("Distance")?)?;
         Ok(PuzzleInputPart2 { time, distance })
     }
 }
+
+/// The time and distance lines of the input.
+fn two_lines(s: &str) -> parse::Result<(Text<'_>, Text<'_>)> {
+    let input = Text::new(s).trim();
+    let mut lines = input.lines();
+    let time = lines
+        .next()
+        .ok_or_else(|| input.error("expected a line of times"))?;
+    let distance = lines
+        .next()
+        .ok_or_else(|| input.end().error("expected a line of distances"))?;
+    Ok((time, distance))
+}
+
+/// Read the digits of `values` as a single number, ignoring the spaces between them.
+fn kerned_number(values: Text) -> parse::Result<i64> {
+    values
+        .as_str()
+        .replace(' ', "")
+        .parse::<i64>()
+        .map_err(|e| values.error(format!("invalid number `{}`: {e}", values.as_str())))
+}
+
 pub fn distance_traveled(time: i64, charge: i64) -> i64 {
     let remaining = time - charge;
     remaining * charge
 }
 
+/// Parse the races both ways, as each part reads the input differently.
+pub fn check(input: &str) -> Result<()> {
+    input.parse::<PuzzleInput>()?;
+    input.parse::<PuzzleInputPart2>()?;
+    Ok(())
+}
+
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i64 {
     let puzzle_input = &input.parse::<PuzzleInput>().unwrap();
-    // dbg!(puzzle_input);
+    debug!(?puzzle_input);
 
     let mut score = 1;
     for i in 0..puzzle_input.times.len() {
@@ -81,14 +99,28 @@ pub fn part1(input: &str) -> i64 {
                 winning_count += 1;
             }
         }
+        trace!(time, distance, winning_count, "race");
         score *= winning_count;
     }
     score
 }
 
+/// Same as [`part1`], solving the polynomial of each race instead of trying every charge.
+#[instrument(skip_all)]
+pub fn part1_poly(input: &str) -> i64 {
+    let puzzle_input = input.parse::<PuzzleInput>().unwrap();
+    puzzle_input
+        .times
+        .iter()
+        .zip(&puzzle_input.distances)
+        .map(|(&time, &distance)| solve_poly(time, distance))
+        .product()
+}
+
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i64 {
     let puzzle_input = &input.parse::<PuzzleInputPart2>().unwrap();
-    dbg!(puzzle_input);
+    debug!(?puzzle_input);
     solve_poly(puzzle_input.time, puzzle_input.distance)
 }
 
@@ -100,16 +132,54 @@ pub fn solve_poly(time: i64, distance: i64) -> i64 {
     // -charge**2 + time * charge - distance >= 0
 
     let det = time.pow(2) - 4 * (-1) * (-distance);
+    if det < 0 {
+        return 0;
+    }
     let det = det as f64;
-    let det = dbg!(det);
-    let upper = (-time as f64 + det.sqrt()) / 2.;
-    let lower = (-time as f64 - det.sqrt()) / 2.;
-    upper as i64 - (lower as i64)
+    trace!(time, distance, det, "solving the race polynomial");
+    // the first charge that beats the record is right after the lower root.
+    // The float root can be off by one on big races, and a charge landing exactly
+    // on the record doesn't beat it, so we adjust it on the integers
+    let mut lower = ((time as f64 - det.sqrt()) / 2.).floor() as i64;
+    while lower > 0 && distance_traveled(time, lower - 1) > distance {
+        lower -= 1;
+    }
+    while distance_traveled(time, lower) <= distance {
+        lower += 1;
+        if lower > time / 2 {
+            return 0;
+        }
+    }
+    // the winning charges are symmetric around time / 2
+    time - 2 * lower + 1
+}
+
+/// Generate up to `size` races, each with a record that can be beaten.
+/// There are at most 4 races, as part 2 reads all of them as a single big one.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let races = (0..size.clamp(1, 4))
+        .map(|_| {
+            let time = rng.gen_range(2..100i64);
+            let best = (time / 2) * (time - time / 2);
+            (time, rng.gen_range(0..best))
+        })
+        .collect::<Vec<_>>();
+    let times = races
+        .iter()
+        .map(|(t, _)| format!(" {t:>4}"))
+        .collect::<String>();
+    let distances = races
+        .iter()
+        .map(|(_, d)| format!(" {d:>4}"))
+        .collect::<String>();
+    format!("Time:    {times}\nDistance:{distances}\n")
 }
 
 #[cfg(test)]
 mod test_day_6 {
-    use super::{part1, part2};
+    use super::{
+        distance_traveled, part1, part1_poly, part2, solve_poly, PuzzleInput, PuzzleInputPart2,
+    };
     use crate::puzzle_inputs;
 
     /// Here 114 and 58 are not adjacent to anything
@@ -130,12 +200,33 @@ Distance:  9  40  200
 
     #[test]
     fn day6_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(6, 1);
+        let input1 = puzzle_inputs::real_input!(6);
         let res = part1(&input1);
         k9::snapshot!(res, "505494");
         k9::assert_equal!(res, 505494);
     }
 
+    #[test]
+    fn day6_poly_exact_record() {
+        // charging 10 or 20ms exactly matches the record, which doesn't beat it
+        k9::assert_equal!(solve_poly(30, 200), 9);
+        k9::assert_equal!(solve_poly(4, 4), 0);
+        k9::assert_equal!(part1_poly(EXAMPLE_INPUT_PART_1), EXAMPLE_OUTPUT_PART_1);
+    }
+
+    #[test]
+    fn day6_poly_matches_trying_every_charge() {
+        // including the records that can only be matched, and the ones that can't even be
+        for time in 0..60 {
+            for distance in 0..=time * time / 4 + 1 {
+                let winning = (0..=time)
+                    .filter(|&charge| distance_traveled(time, charge) > distance)
+                    .count() as i64;
+                k9::assert_equal!(solve_poly(time, distance), winning, "{time} {distance}");
+            }
+        }
+    }
+
     #[test]
     fn day6_p2_example() {
         // let res = solve_poly(71530, 940200);
@@ -147,10 +238,28 @@ Distance:  9  40  200
     #[test]
     fn day6_p2_real() {
         // let res = solve_poly(40829166, 277133813491063);
-        let input2 = puzzle_inputs::get_puzzle_input(6, 1);
+        let input2 = puzzle_inputs::real_input!(6);
         let res = part2(&input2);
 
         k9::snapshot!(res, "23632299");
         k9::assert_equal!(res, 23632299);
     }
+
+    #[test]
+    fn day6_parse_errors() {
+        let err = "Time: 7 15\nDistance: 9"
+            .parse::<PuzzleInput>()
+            .unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 2, column 1: expected 2 distances, one for each time, found 1"
+        );
+        let err = "Time: 7 15\nDistance 9"
+            .parse::<PuzzleInputPart2>()
+            .unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 2, column 1: expected `:` in `Distance 9`"
+        );
+    }
 }
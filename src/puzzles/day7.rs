@@ -1,7 +1,9 @@
 use anyhow::{Error, Result};
 use itertools::Itertools;
+use rand::Rng;
 use smallvec::SmallVec;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use tracing::{instrument, trace};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum HandStrength {
@@ -314,6 +316,7 @@ struct PuzzleInput {
 impl std::str::FromStr for PuzzleInput {
     type Err = Error;
 
+    #[instrument(name = "parse", skip_all)]
     fn from_str(s: &str) -> Result<PuzzleInput> {
         let lines = s.trim().lines();
         let hands = lines
@@ -337,6 +340,7 @@ struct PuzzleInputP2 {
 impl std::str::FromStr for PuzzleInputP2 {
     type Err = Error;
 
+    #[instrument(name = "parse", skip_all)]
     fn from_str(s: &str) -> Result<PuzzleInputP2> {
         let lines = s.trim().lines();
         let hands = lines
@@ -352,6 +356,7 @@ impl std::str::FromStr for PuzzleInputP2 {
     }
 }
 
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     let puzzle = input.parse::<PuzzleInput>().unwrap();
     puzzle
@@ -359,13 +364,11 @@ pub fn part1(input: &str) -> i32 {
         .iter()
         .sorted_by(|a, b| a.0.cmp(&b.0))
         .enumerate()
-        .map(|f| {
-            println!("{:?}", f);
-            f
-        })
+        .inspect(|(rank, (hand, bet))| trace!(rank, ?hand, bet, "ranked hand"))
         .fold(0, |acc, (i, (_, bet))| acc + bet * (i + 1) as i32)
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i32 {
     let puzzle = input.parse::<PuzzleInputP2>().unwrap();
     puzzle
@@ -373,17 +376,32 @@ pub fn part2(input: &str) -> i32 {
         .iter()
         .sorted_by(|a, b| a.0.cmp(&b.0))
         .enumerate()
-        .map(|f| {
-            println!("{:?}", f);
-            f
-        })
+        .inspect(|(rank, (hand, bet))| trace!(rank, ?hand, bet, "ranked hand"))
         .fold(0, |acc, (i, (_, bet))| acc + bet * (i + 1) as i32)
 }
 
+/// Generate `size` distinct hands with their bids.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    const CARDS: &[u8] = b"23456789TJQKA";
+    let mut hands = HashSet::new();
+    let mut res = String::new();
+    // there are only 13^5 different hands
+    while hands.len() < size.min(CARDS.len().pow(5)) {
+        let hand = (0..5)
+            .map(|_| CARDS[rng.gen_range(0..CARDS.len())] as char)
+            .collect::<String>();
+        if hands.insert(hand.clone()) {
+            res += &format!("{hand} {}\n", rng.gen_range(1..=1000));
+        }
+    }
+    res
+}
+
 #[cfg(test)]
 mod test_day_7 {
-    use super::{part1, part2};
+    use super::{generate, part1, part2};
     use crate::puzzle_inputs;
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
     /// Here 114 and 58 are not adjacent to anything
     const EXAMPLE_INPUT_PART_1: &str = "\
@@ -406,7 +424,7 @@ QQQJA 483";
 
     #[test]
     fn day7_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(7, 1);
+        let input1 = puzzle_inputs::real_input!(7);
         let res = part1(&input1);
         k9::snapshot!(res, "249390788");
         k9::assert_equal!(res, 249390788);
@@ -421,9 +439,22 @@ QQQJA 483";
 
     #[test]
     fn day7_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(7, 1);
+        let input2 = puzzle_inputs::real_input!(7);
         let res = part2(&input2);
         k9::snapshot!(res, "248750248");
         k9::assert_equal!(res, 248750248);
     }
+
+    #[test]
+    fn day7_generated_order_does_not_matter() {
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let input = generate(&mut rng, 100);
+            let mut lines = input.lines().collect::<Vec<_>>();
+            lines.shuffle(&mut rng);
+            let shuffled = lines.join("\n");
+            k9::assert_equal!(part1(&shuffled), part1(&input));
+            k9::assert_equal!(part2(&shuffled), part2(&input));
+        }
+    }
 }
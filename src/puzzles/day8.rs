@@ -1,7 +1,12 @@
 use anyhow::{Error, Result};
 use itertools::Itertools;
-use num::integer::lcm;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::HashMap;
+use tracing::{debug, instrument, trace};
+
+use crate::utils::parse::{self, Text};
+use crate::utils::{self, cycle::Cycle, numtheory};
 
 #[derive(Debug)]
 struct PuzzeInput {
@@ -12,50 +17,63 @@ struct PuzzeInput {
 impl std::str::FromStr for PuzzeInput {
     type Err = Error;
 
+    #[instrument(name = "parse", skip_all)]
     fn from_str(s: &str) -> Result<PuzzeInput> {
-        let mut lines = s.lines();
+        let input = Text::new(s);
+        let mut sections = input.sections();
+        let instructions = sections
+            .next()
+            .ok_or_else(|| input.error("expected a line of instructions"))?
+            .trim();
+        let right = instructions
+            .as_str()
+            .char_indices()
+            .map(|(i, c)| match c {
+                'L' => Ok(false),
+                'R' => Ok(true),
+                _ => Err(instructions
+                    .slice(i..i + c.len_utf8())
+                    .error(format!("expected `L` or `R`, found `{c}`"))
+                    .with_hint("the first line says which way to go at each node, left or right")),
+            })
+            .collect::<parse::Result<Vec<bool>>>()?;
 
-        let right = lines
+        let nodes = sections
             .next()
-            .unwrap()
-            .chars()
-            .map(|c| c == 'R')
-            .collect::<Vec<bool>>();
-
-        // let map = HashMap::<String, (String, String)>::new();
-        let map = lines
-            .skip(1)
-            .into_iter()
+            .ok_or_else(|| input.end().error("expected the nodes of the network"))?;
+        let map = nodes
+            .lines()
             .map(|line| {
-                let mut parts = line.split(" = (");
-                let key = parts.next().unwrap();
-                let (left, right) = parts
-                    .next()
-                    .unwrap()
-                    .split(')')
-                    .next()
-                    .unwrap()
-                    .split(", ")
-                    .collect_tuple()
-                    .unwrap();
-                (key.to_string(), (left.to_string(), right.to_string()))
+                let (key, targets) = line.trim().split_once(" = ")?;
+                let (left, right) = targets
+                    .strip_prefix("(")?
+                    .strip_suffix(")")?
+                    .split_once(", ")?;
+                Ok((
+                    key.as_str().to_string(),
+                    (left.as_str().to_string(), right.as_str().to_string()),
+                ))
             })
-            .collect::<HashMap<String, (String, String)>>();
+            .collect::<parse::Result<HashMap<String, (String, String)>>>()?;
 
         Ok(PuzzeInput { right, map })
     }
 }
 
+/// Parse the network without walking it.
+pub fn check(input: &str) -> Result<()> {
+    input.parse::<PuzzeInput>().map(|_| ())
+}
+
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     let input = input.parse::<PuzzeInput>().unwrap();
 
-    // println!("{:?}", input);
-
     let mut current = "AAA".to_string();
     let mut steps = 0;
 
     for right in input.right.into_iter().cycle() {
-        // println!("{steps}: {current}");
+        trace!(steps, current, "step");
         current = if right {
             input.map.get(&current).unwrap().1.clone()
         } else {
@@ -69,11 +87,10 @@ pub fn part1(input: &str) -> i32 {
     steps
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> usize {
     let input = input.parse::<PuzzeInput>().unwrap();
 
-    // println!("{:?}", input);
-
     let starts = input
         .map
         .keys()
@@ -81,32 +98,112 @@ pub fn part2(input: &str) -> usize {
         .map(|k| k.to_string())
         .collect::<Vec<String>>();
 
-    println!("{:?}", starts.len());
-
-    let mut step_counts = Vec::<usize>::new();
-
-    for mut current in starts {
-        let mut steps = 0usize;
-        for right in input.right.clone().into_iter().cycle() {
-            // println!("{steps}: {}", current[i]);
-            current = if right {
-                input.map.get(&current).unwrap().1.clone()
-            } else {
-                input.map.get(&current).unwrap().0.clone()
-            };
-            steps += 1;
-            if current.ends_with('Z') {
-                break;
-            }
+    debug!(ghosts = starts.len(), "found starting nodes");
+
+    let ghosts = starts
+        .iter()
+        .map(|start| {
+            let (walk, ends) = end_node_times(&input, start);
+            debug!(
+                start,
+                loop_start = walk.start,
+                period = walk.period,
+                ends = ends.len(),
+                "found the loop of a ghost"
+            );
+            (walk, ends)
+        })
+        .collect::<Vec<_>>();
+
+    // before every ghost is looping, check the steps at which one of them is on an end node
+    let before_loops = ghosts
+        .iter()
+        .flat_map(|(walk, ends)| ends.iter().copied().filter(|&t| t < walk.start))
+        .filter(|&t| {
+            ghosts
+                .iter()
+                .all(|(walk, ends)| ends.contains(&walk.reduce(t)))
+        })
+        .min();
+    // after that, each end node in the loop of a ghost comes back every period, and any
+    // combination of them may be the first to happen together
+    let in_loops = ghosts
+        .iter()
+        .map(|(walk, ends)| {
+            ends.iter()
+                .filter(|&&t| t >= walk.start)
+                .map(|&t| (t as i128, walk.period as i128))
+                .collect::<Vec<_>>()
+        })
+        .multi_cartesian_product()
+        .filter_map(|events| numtheory::first_common_time(&events))
+        .min();
+    before_loops
+        .or(in_loops.map(|t| t as usize))
+        .expect("the ghosts are never all on end nodes at once")
+}
+
+/// Where the ghost on `node` goes next, at instruction `i`.
+fn next_node<'a>(input: &'a PuzzeInput, (node, i): (&str, usize)) -> (&'a str, usize) {
+    let (left, right) = &input.map[node];
+    let next = if input.right[i] { right } else { left };
+    (next.as_str(), (i + 1) % input.right.len())
+}
+
+/// The loop the walk of the ghost starting on `start` ends up in, and the steps at which
+/// the ghost is on an end node until it has gone around the loop once.
+fn end_node_times(input: &PuzzeInput, start: &str) -> (Cycle, Vec<usize>) {
+    let walk = utils::cycle::brent((start, 0), |&state| next_node(input, state));
+    let mut state = (start, 0);
+    let mut ends = vec![];
+    for t in 0..walk.start + walk.period {
+        trace!(t, node = state.0, "step");
+        if state.0.ends_with('Z') {
+            ends.push(t);
+        }
+        state = next_node(input, state);
+    }
+    (walk, ends)
+}
+
+/// Generate a network where each ghost loops back to its start after reaching its end node,
+/// as in the real inputs, with up to `size` nodes per ghost.
+/// Ghost 0 goes from `AAA` to `ZZZ`, so the network works for both parts.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let letter = |i: usize| (b'B' + i as u8) as char;
+    let instructions = (0..rng.gen_range(1..=10))
+        .map(|_| if rng.gen_bool(0.5) { 'L' } else { 'R' })
+        .collect::<String>();
+    let mut nodes = vec![];
+    for ghost in 0..rng.gen_range(1..=5) {
+        let prefix = if ghost == 0 { 'A' } else { letter(ghost - 1) };
+        let (start, end) = if ghost == 0 {
+            ("AAA".to_string(), "ZZZ".to_string())
+        } else {
+            (format!("{prefix}{prefix}A"), format!("{prefix}{prefix}Z"))
+        };
+        // the loop is start -> path[0] -> ... -> end -> path[0]
+        let len = rng.gen_range(1..=size.clamp(1, 24 * 24));
+        let mut path = (1..len)
+            .map(|i| format!("{prefix}{}{}", letter(i / 24), letter(i % 24)))
+            .collect::<Vec<_>>();
+        path.push(end);
+        nodes.push((start, path[0].clone()));
+        for (i, node) in path.iter().enumerate() {
+            nodes.push((node.clone(), path[(i + 1) % path.len()].clone()));
         }
-        step_counts.push(steps);
     }
-    step_counts.iter().fold(1usize, |acc, &x| lcm(acc, x))
+    nodes.shuffle(rng);
+    let network = nodes
+        .into_iter()
+        .map(|(node, next)| format!("{node} = ({next}, {next})"))
+        .join("\n");
+    format!("{instructions}\n\n{network}\n")
 }
 
 #[cfg(test)]
 mod test_day8 {
-    use super::{part1, part2};
+    use super::{part1, part2, PuzzeInput};
     use crate::puzzle_inputs;
 
     const EXAMPLE_INPUT_PART_1: &str = "\
@@ -158,7 +255,7 @@ XXX = (XXX, XXX)";
 
     #[test]
     fn day8_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(8, 1);
+        let input1 = puzzle_inputs::real_input!(8);
         let res = part1(&input1);
         k9::snapshot!(res, "14429");
         k9::assert_equal!(res, 14429);
@@ -173,9 +270,58 @@ XXX = (XXX, XXX)";
 
     #[test]
     fn day8_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(8, 1);
+        let input2 = puzzle_inputs::real_input!(8);
         let res = part2(&input2);
         k9::snapshot!(res, "10921547990923");
         k9::assert_equal!(res, 10921547990923usize);
     }
+
+    #[test]
+    fn day8_p2_offset_loops() {
+        // the first ghost is on 11Z at steps 2, 5, 8... and the second one on 22Z at steps
+        // 1, 5, 9..., so the lcm of their first end nodes would say 2
+        let input = "\
+L
+
+11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11C, 11C)
+11C = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (22C, 22C)
+22C = (22D, 22D)
+22D = (22Z, 22Z)
+";
+        k9::assert_equal!(part2(input), 5);
+        // both ghosts are on end nodes before the second one starts looping
+        let input = "\
+L
+
+11A = (11Z, 11Z)
+11Z = (11Z, 11Z)
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (22B, 22B)
+";
+        k9::assert_equal!(part2(input), 1);
+    }
+
+    #[test]
+    fn day8_parse_errors() {
+        let err = "LRX\n\nAAA = (BBB, BBB)\n"
+            .parse::<PuzzeInput>()
+            .unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 1, column 3: expected `L` or `R`, found `X`"
+        );
+        let err = "LR\n\nAAA = (BBB, BBB)\nBBB = (AAA, AAA\n"
+            .parse::<PuzzeInput>()
+            .unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "line 4, column 16: expected `)` at the end of `AAA, AAA`"
+        );
+    }
 }
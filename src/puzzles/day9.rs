@@ -1,3 +1,7 @@
+use rand::Rng;
+use tracing::{instrument, trace};
+
+#[instrument(name = "parse", skip_all)]
 pub fn parse_input(input: &str) -> Vec<Vec<i32>> {
     input
         .lines()
@@ -9,6 +13,7 @@ pub fn parse_input(input: &str) -> Vec<Vec<i32>> {
         .collect::<Vec<Vec<i32>>>()
 }
 
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     let input = parse_input(input);
 
@@ -25,7 +30,9 @@ pub fn part1(input: &str) -> i32 {
                     .collect::<Vec<i32>>(),
             );
         }
-        total += differences.iter().map(|d| d.last().unwrap()).sum::<i32>();
+        let next = differences.iter().map(|d| d.last().unwrap()).sum::<i32>();
+        trace!(next, depth = differences.len(), "extrapolated next value");
+        total += next;
     }
     total
 }
@@ -34,6 +41,7 @@ fn is_constant(seq: &[i32]) -> bool {
     seq.is_empty() || seq.iter().all(|&n| n == seq[0])
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i32 {
     let input = parse_input(input);
     let mut total = 0;
@@ -60,10 +68,32 @@ pub fn part2(input: &str) -> i32 {
     total
 }
 
+/// Generate `size` sequences, each one being the values of a random polynomial of degree at most 4.
+pub fn generate(rng: &mut impl Rng, size: usize) -> String {
+    let mut res = String::new();
+    for _ in 0..size {
+        let coefficients = (0..rng.gen_range(1..=5))
+            .map(|_| rng.gen_range(-5..=5))
+            .collect::<Vec<i32>>();
+        // enough values for the differences to become constant
+        let len = rng.gen_range(coefficients.len() + 1..=21) as i32;
+        let values = (0..len).map(|x| {
+            coefficients
+                .iter()
+                .rev()
+                .fold(0, |acc, coefficient| acc * x + coefficient)
+        });
+        res += &values.map(|v| v.to_string()).collect::<Vec<_>>().join(" ");
+        res.push('\n');
+    }
+    res
+}
+
 #[cfg(test)]
 mod test_day9 {
-    use super::{part1, part2};
+    use super::{generate, part1, part2};
     use crate::puzzle_inputs;
+    use rand::{rngs::StdRng, SeedableRng};
 
     const EXAMPLE_INPUT_PART_1: &str = "\
 0 3 6 9 12 15
@@ -85,7 +115,7 @@ mod test_day9 {
 
     #[test]
     fn day9_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(9, 1);
+        let input1 = puzzle_inputs::real_input!(9);
         let res = part1(&input1);
         k9::snapshot!(res, "1995001648");
         k9::assert_equal!(res, 1995001648);
@@ -100,9 +130,22 @@ mod test_day9 {
 
     #[test]
     fn day9_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(9, 1);
+        let input2 = puzzle_inputs::real_input!(9);
         let res = part2(&input2);
         k9::snapshot!(res, "988");
         k9::assert_equal!(res, 988);
     }
+
+    #[test]
+    fn day9_generated_backwards_is_forwards_reversed() {
+        for seed in 0..20 {
+            let input = generate(&mut StdRng::seed_from_u64(seed), 20);
+            let reversed = input
+                .lines()
+                .map(|l| l.split_whitespace().rev().collect::<Vec<_>>().join(" "))
+                .collect::<Vec<_>>()
+                .join("\n");
+            k9::assert_equal!(part2(&input), part1(&reversed));
+        }
+    }
 }
@@ -7,11 +7,14 @@ use rayon::prelude::*;
 use smallvec::{smallvec, SmallVec};
 use std::collections::{HashMap, HashSet};
 use toml::map;
+use tracing::{debug, instrument, trace};
 
+#[instrument(skip_all)]
 pub fn part1(input: &str) -> i32 {
     todo!()
 }
 
+#[instrument(skip_all)]
 pub fn part2(input: &str) -> i32 {
     todo!()
 }
@@ -37,7 +40,7 @@ mod test_day0 {
 
     #[test]
     fn day0_p1_real() {
-        let input1 = puzzle_inputs::get_puzzle_input(0, 1);
+        let input1 = puzzle_inputs::real_input!(0);
         let res = part1(&input1);
         k9::snapshot!(res);
         k9::assert_equal!(res, EXAMPLE_OUTPUT_PART_1);
@@ -52,7 +55,7 @@ mod test_day0 {
 
     #[test]
     fn day0_p2_real() {
-        let input2 = puzzle_inputs::get_puzzle_input(0, 1);
+        let input2 = puzzle_inputs::real_input!(0);
         let res = part2(&input2);
         k9::snapshot!(res);
         k9::assert_equal!(res, EXAMPLE_OUTPUT_PART_2);
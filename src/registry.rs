@@ -0,0 +1,522 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[allow(unused_imports)] // nothing to import when every day is disabled
+use crate::puzzles::*;
+
+/// The answer to one part of a puzzle. Solutions return whatever integer type
+/// was convenient on the day, this normalizes them so they can be handled uniformly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Signed(i64),
+    Unsigned(u64),
+}
+
+impl From<i32> for Answer {
+    fn from(value: i32) -> Self {
+        Answer::Signed(value as i64)
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(value: i64) -> Self {
+        Answer::Signed(value)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Unsigned(value as u64)
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Signed(v) => write!(f, "{v}"),
+            Answer::Unsigned(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+/// A solution to one part of a puzzle, taking the raw input text.
+pub type Solver = fn(&str) -> Answer;
+
+/// Generates a random valid input for a day, roughly `size` lines or columns big.
+pub type Generator = fn(&mut StdRng, usize) -> String;
+
+/// Parses the input of a day without solving it, to report malformed inputs with their
+/// location instead of panicking halfway through a solution.
+pub type Checker = fn(&str) -> Result<()>;
+
+/// A debugging helper for a day, callable from the REPL without recompiling.
+/// It takes the raw input and the arguments typed after its name.
+pub struct Inspector {
+    pub name: &'static str,
+    /// Arguments expected by the inspector, e.g. `<map> <value>`
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub run: fn(&str, &[&str]) -> Result<String>,
+}
+
+/// Another solution to one part of a day, e.g. a brute force version kept around
+/// to check a faster one against. `crosscheck` runs it alongside the main solution.
+pub struct Implementation {
+    pub name: &'static str,
+    pub part: u8,
+    pub solve: Solver,
+}
+
+/// How the input of a day is split into blank-line-separated sections, so that the
+/// shrinker can remove pieces of it without breaking its structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sections {
+    /// A single block of lines.
+    None,
+    /// A fixed set of sections, each starting with `headers` lines that must be kept,
+    /// e.g. the name of each map on day 5.
+    Fixed { headers: usize },
+    /// Like `Fixed`, but the first section is a `label: values` line whose values come in
+    /// groups of `group`, any of which can be removed, e.g. the seed ranges of day 5.
+    FixedWithValues { group: usize, headers: usize },
+    /// Any number of similar sections, any of which can be removed, e.g. the patterns of day 13.
+    Removable,
+}
+
+/// A solved day, with the solutions to both of its parts.
+pub struct Day {
+    pub day: u8,
+    pub part1: Solver,
+    pub part2: Solver,
+    pub generate: Generator,
+    pub sections: Sections,
+    /// Days whose parsers report errors with their location, see [`Checker`].
+    pub check: Option<Checker>,
+    pub inspectors: &'static [Inspector],
+    pub alternatives: &'static [Implementation],
+}
+
+impl Day {
+    /// Get the solution for `part`, which should be either 1 or 2.
+    pub fn part(&self, part: u8) -> Option<Solver> {
+        match part {
+            1 => Some(self.part1),
+            2 => Some(self.part2),
+            _ => None,
+        }
+    }
+
+    /// All the solutions for `part` with their names, the main one first as `main`.
+    pub fn implementations(&self, part: u8) -> Vec<(&'static str, Solver)> {
+        self.part(part)
+            .map(|main| ("main", main))
+            .into_iter()
+            .chain(
+                self.alternatives
+                    .iter()
+                    .filter(|alt| alt.part == part)
+                    .map(|alt| (alt.name, alt.solve)),
+            )
+            .collect()
+    }
+
+    pub fn inspector(&self, name: &str) -> Option<&'static Inspector> {
+        self.inspectors.iter().find(|i| i.name == name)
+    }
+
+    /// Generate a random input, always the same one for a given `seed`.
+    pub fn generate_input(&self, seed: u64, size: usize) -> String {
+        (self.generate)(&mut StdRng::seed_from_u64(seed), size)
+    }
+}
+
+/// Parse the `i`th argument given to an inspector.
+#[allow(dead_code)] // when the days with inspectors are compiled out
+fn arg<T>(args: &[&str], i: usize) -> Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let value = args
+        .get(i)
+        .ok_or_else(|| anyhow!("Missing argument {}", i + 1))?;
+    value
+        .parse()
+        .map_err(|e| anyhow!("Invalid argument {value}: {e}"))
+}
+
+/// All the solved days, in order. New days need to be added here to be reachable from the CLI.
+pub static DAYS: &[Day] = &[
+    #[cfg(feature = "day1")]
+    Day {
+        day: 1,
+        part1: |input| day1::part_1(input).into(),
+        part2: |input| day1::part_2(input).into(),
+        generate: |rng, size| day1::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day2")]
+    Day {
+        day: 2,
+        part1: |input| day2::part1(input).into(),
+        part2: |input| day2::part2(input).into(),
+        generate: |rng, size| day2::generate(rng, size),
+        sections: Sections::None,
+        check: Some(day2::check),
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day3")]
+    Day {
+        day: 3,
+        part1: |input| day3::part1(input).into(),
+        part2: |input| day3::part2(input).into(),
+        generate: |rng, size| day3::generate(rng, size),
+        sections: Sections::None,
+        check: Some(day3::check),
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day4")]
+    Day {
+        day: 4,
+        part1: |input| day4::part1(input).into(),
+        part2: |input| day4::part2(input).into(),
+        generate: |rng, size| day4::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day5")]
+    Day {
+        day: 5,
+        part1: |input| day5::part1(input).into(),
+        part2: |input| day5::part2_take2(input).into(),
+        generate: |rng, size| day5::generate(rng, size),
+        sections: Sections::FixedWithValues {
+            group: 2,
+            headers: 1,
+        },
+        check: Some(day5::check),
+        alternatives: &[Implementation {
+            name: "brute_force",
+            part: 2,
+            solve: |input| day5::part2(input).into(),
+        }],
+        inspectors: &[
+            Inspector {
+                name: "summary",
+                usage: "",
+                help: "Show the seeds and the size of each map",
+                run: |input, _| day5::summary(input),
+            },
+            Inspector {
+                name: "map",
+                usage: "<map> <value>",
+                help: "Look up a value in a map, e.g. `map seed-to-soil 79`",
+                run: |input, args| {
+                    day5::map_value(input, &arg::<String>(args, 0)?, arg(args, 1)?)
+                        .map(|v| v.to_string())
+                },
+            },
+            Inspector {
+                name: "trace",
+                usage: "<seed>",
+                help: "Follow a seed through all the maps",
+                run: |input, args| day5::trace_seed(input, arg(args, 0)?),
+            },
+        ],
+    },
+    #[cfg(feature = "day6")]
+    Day {
+        day: 6,
+        part1: |input| day6::part1(input).into(),
+        part2: |input| day6::part2(input).into(),
+        generate: |rng, size| day6::generate(rng, size),
+        sections: Sections::None,
+        check: Some(day6::check),
+        alternatives: &[Implementation {
+            name: "poly",
+            part: 1,
+            solve: |input| day6::part1_poly(input).into(),
+        }],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day7")]
+    Day {
+        day: 7,
+        part1: |input| day7::part1(input).into(),
+        part2: |input| day7::part2(input).into(),
+        generate: |rng, size| day7::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day8")]
+    Day {
+        day: 8,
+        part1: |input| day8::part1(input).into(),
+        part2: |input| day8::part2(input).into(),
+        generate: |rng, size| day8::generate(rng, size),
+        sections: Sections::Fixed { headers: 0 },
+        check: Some(day8::check),
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day9")]
+    Day {
+        day: 9,
+        part1: |input| day9::part1(input).into(),
+        part2: |input| day9::part2(input).into(),
+        generate: |rng, size| day9::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day10")]
+    Day {
+        day: 10,
+        part1: |input| day10::part1(input).into(),
+        part2: |input| day10::part2(input).into(),
+        generate: |rng, size| day10::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day11")]
+    Day {
+        day: 11,
+        part1: |input| day11::part1(input).into(),
+        part2: |input| day11::part2(input, 1_000_000).into(),
+        generate: |rng, size| day11::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[Inspector {
+            name: "galaxies",
+            usage: "<dist_mul>",
+            help: "List the galaxies after expanding the universe",
+            run: |input, args| Ok(day11::expanded_galaxies(input, arg(args, 0)?)),
+        }],
+    },
+    #[cfg(feature = "day12")]
+    Day {
+        day: 12,
+        part1: |input| day12::part1(input).into(),
+        part2: |input| day12::part2(input).into(),
+        generate: |rng, size| day12::generate(rng, size),
+        sections: Sections::None,
+        check: Some(day12::check),
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day13")]
+    Day {
+        day: 13,
+        part1: |input| day13::part1(input).into(),
+        part2: |input| day13::part2(input).into(),
+        generate: |rng, size| day13::generate(rng, size),
+        sections: Sections::Removable,
+        check: None,
+        alternatives: &[],
+        inspectors: &[Inspector {
+            name: "reflections",
+            usage: "",
+            help: "Show the reflection lines of each pattern",
+            run: |input, _| Ok(day13::describe_reflections(input)),
+        }],
+    },
+    #[cfg(feature = "day14")]
+    Day {
+        day: 14,
+        part1: |input| day14::part1(input).into(),
+        part2: |input| day14::part2(input).into(),
+        generate: |rng, size| day14::generate(rng, size),
+        sections: Sections::None,
+        check: Some(day14::check),
+        alternatives: &[],
+        inspectors: &[Inspector {
+            name: "cycles",
+            usage: "<n>",
+            help: "Show the grid after n spin cycles",
+            run: |input, args| Ok(day14::grid_after_cycles(input, arg(args, 0)?)),
+        }],
+    },
+    #[cfg(feature = "day15")]
+    Day {
+        day: 15,
+        part1: |input| day15::part1(input).into(),
+        part2: |input| day15::part2(input).into(),
+        generate: |rng, size| day15::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[],
+    },
+    #[cfg(feature = "day16")]
+    Day {
+        day: 16,
+        part1: |input| day16::part1(input).into(),
+        part2: |input| day16::part2(input).into(),
+        generate: |rng, size| day16::generate(rng, size),
+        sections: Sections::None,
+        check: None,
+        alternatives: &[],
+        inspectors: &[Inspector {
+            name: "beam",
+            usage: "<north|south|east|west> <index>",
+            help: "Trace a beam entering from an edge at the given row or column",
+            run: |input, args| day16::trace_beam(input, &arg::<String>(args, 0)?, arg(args, 1)?),
+        }],
+    },
+];
+
+/// Solved days that were left out of this build, by disabling their cargo feature.
+pub static DISABLED_DAYS: &[u8] = &[
+    #[cfg(not(feature = "day1"))]
+    1,
+    #[cfg(not(feature = "day2"))]
+    2,
+    #[cfg(not(feature = "day3"))]
+    3,
+    #[cfg(not(feature = "day4"))]
+    4,
+    #[cfg(not(feature = "day5"))]
+    5,
+    #[cfg(not(feature = "day6"))]
+    6,
+    #[cfg(not(feature = "day7"))]
+    7,
+    #[cfg(not(feature = "day8"))]
+    8,
+    #[cfg(not(feature = "day9"))]
+    9,
+    #[cfg(not(feature = "day10"))]
+    10,
+    #[cfg(not(feature = "day11"))]
+    11,
+    #[cfg(not(feature = "day12"))]
+    12,
+    #[cfg(not(feature = "day13"))]
+    13,
+    #[cfg(not(feature = "day14"))]
+    14,
+    #[cfg(not(feature = "day15"))]
+    15,
+    #[cfg(not(feature = "day16"))]
+    16,
+];
+
+/// Look up a solved day by its number.
+pub fn get_day(day: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.day == day)
+}
+
+/// Same as [`get_day`], with an error telling why the day is missing.
+pub fn lookup(day: u8) -> Result<&'static Day> {
+    if DISABLED_DAYS.contains(&day) {
+        bail!("Day {day} is not compiled in, enable it with `--features day{day}`");
+    }
+    get_day(day).ok_or_else(|| anyhow!("Day {day} is not solved"))
+}
+
+#[cfg(test)]
+mod test_registry {
+    use super::*;
+
+    #[test]
+    fn days_are_sorted_and_unique() {
+        assert!(DAYS.windows(2).all(|w| w[0].day < w[1].day));
+    }
+
+    #[test]
+    #[cfg(feature = "day5")]
+    fn get_day_finds_registered_days() {
+        k9::assert_equal!(get_day(5).map(|d| d.day), Some(5));
+        assert!(get_day(0).is_none());
+    }
+
+    #[test]
+    fn lookup_explains_missing_days() {
+        let err = lookup(0).err().unwrap().to_string();
+        k9::assert_equal!(err, "Day 0 is not solved");
+        for &day in DISABLED_DAYS {
+            assert!(get_day(day).is_none());
+            let err = lookup(day).err().unwrap().to_string();
+            assert!(err.contains(&format!("--features day{day}")), "{err}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "day9")]
+    fn run_example_through_registry() {
+        let day = get_day(9).unwrap();
+        let input = "0 3 6 9 12 15\n1 3 6 10 15 21\n10 13 16 21 30 45\n";
+        k9::assert_equal!((day.part(1).unwrap())(input), Answer::Signed(114));
+        k9::assert_equal!((day.part(2).unwrap())(input), Answer::Signed(2));
+        assert!(day.part(3).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "day14")]
+    fn run_inspector_through_registry() {
+        let day = get_day(14).unwrap();
+        let cycles = day.inspector("cycles").unwrap();
+        assert!((cycles.run)("O.\n.#", &["1"]).is_ok());
+        assert!((cycles.run)("O.\n.#", &[]).is_err());
+        assert!((cycles.run)("O.\n.#", &["x"]).is_err());
+        assert!(day.inspector("nope").is_none());
+    }
+
+    #[test]
+    fn solve_generated_inputs() {
+        for day in DAYS {
+            for seed in 0..5 {
+                let input = day.generate_input(seed, 8);
+                k9::assert_equal!(input, day.generate_input(seed, 8));
+                for part in [1, 2] {
+                    let res = std::panic::catch_unwind(|| (day.part(part).unwrap())(&input));
+                    assert!(
+                        res.is_ok(),
+                        "day {} part {part} panicked on seed {seed}:\n{input}",
+                        day.day
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "day5")]
+    fn implementations_of_a_part() {
+        let day = get_day(5).unwrap();
+        let names = |part| {
+            day.implementations(part)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+        k9::assert_equal!(names(1), vec!["main"]);
+        k9::assert_equal!(names(2), vec!["main", "brute_force"]);
+        assert!(names(3).is_empty());
+    }
+
+    #[test]
+    fn inspector_names_are_unique() {
+        for day in DAYS {
+            let names = day.inspectors.iter().map(|i| i.name).collect::<Vec<_>>();
+            let unique = names.iter().collect::<std::collections::HashSet<_>>();
+            k9::assert_equal!(names.len(), unique.len());
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::isolate;
+use crate::puzzle_inputs::{read_input, try_get_puzzle_input};
+use crate::registry::{self, Day};
+
+/// What to do after executing a line.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Output(String),
+    Quit,
+}
+
+/// The state of the REPL: the day we're exploring and the input it works on.
+pub struct Session {
+    day: &'static Day,
+    input: String,
+}
+
+impl Session {
+    pub fn new(day: u8, input: String) -> Result<Self> {
+        let day = registry::lookup(day)?;
+        Ok(Self { day, input })
+    }
+
+    /// Describe the loaded input. Days with a `summary` inspector use it to show the parsed input.
+    pub fn describe_input(&self) -> Result<String> {
+        let mut res = format!(
+            "Day {}: loaded {} lines",
+            self.day.day,
+            self.input.lines().count()
+        );
+        if let Some(summary) = self.day.inspector("summary") {
+            res += "\n";
+            res += &(summary.run)(&self.input, &[])?;
+        }
+        Ok(res)
+    }
+
+    fn help(&self) -> String {
+        let mut res = "\
+Commands:
+  help                  Show this message
+  day <n> [path]        Switch to another day, with its cached input or the given file
+  load <path>           Load another input file for the current day
+  part1, part2          Run a solution on the loaded input
+  quit                  Exit the REPL"
+            .to_string();
+        if self.day.inspectors.is_empty() {
+            res += &format!("\nDay {} has no inspectors", self.day.day);
+        } else {
+            res += &format!("\nInspectors for day {}:", self.day.day);
+            for inspector in self.day.inspectors {
+                let usage = format!("{} {}", inspector.name, inspector.usage);
+                res += &format!("\n  {usage:<22}{}", inspector.help);
+            }
+        }
+        res
+    }
+
+    /// Execute a single line typed by the user.
+    pub fn execute(&mut self, line: &str) -> Result<Outcome> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(Outcome::Output(String::new()));
+        };
+        let args = words.collect::<Vec<_>>();
+
+        let output = match command {
+            "help" => self.help(),
+            "quit" | "exit" => return Ok(Outcome::Quit),
+            "day" => {
+                let day = args
+                    .first()
+                    .ok_or_else(|| anyhow!("Usage: day <n> [path]"))?
+                    .parse::<u8>()?;
+                let input = match args.get(1) {
+                    Some(path) => read_input(Path::new(path))?,
+                    None => try_get_puzzle_input(day as i32, 1)?,
+                };
+                *self = Session::new(day, input)?;
+                self.describe_input()?
+            }
+            "load" => {
+                let path = args.first().ok_or_else(|| anyhow!("Usage: load <path>"))?;
+                self.input = read_input(Path::new(path))?;
+                self.describe_input()?
+            }
+            "part1" | "part2" => {
+                let solver = if command == "part1" {
+                    self.day.part1
+                } else {
+                    self.day.part2
+                };
+                // a malformed input usually makes the solution panic, which mustn't end the session
+                let status = isolate::solve(solver, &self.input, false);
+                if !status.is_solved() {
+                    bail!("{command} {status}");
+                }
+                status.to_string()
+            }
+            name => {
+                let inspector = self.day.inspector(name).ok_or_else(|| {
+                    anyhow!("Unknown command {name}, type `help` to list the commands")
+                })?;
+                (inspector.run)(&self.input, &args)?
+            }
+        };
+        Ok(Outcome::Output(output))
+    }
+}
+
+/// Read commands from `reader` until it is exhausted or the user quits.
+/// Errors are shown to the user rather than ending the session.
+pub fn run<R: BufRead, W: Write>(session: &mut Session, reader: R, mut writer: W) -> Result<()> {
+    let prompt = |w: &mut W, day: u8| -> Result<()> {
+        write!(w, "day{day}> ")?;
+        w.flush()?;
+        Ok(())
+    };
+    prompt(&mut writer, session.day.day)?;
+    for line in reader.lines() {
+        match session.execute(&line?) {
+            Ok(Outcome::Quit) => return Ok(()),
+            Ok(Outcome::Output(output)) if output.is_empty() => {}
+            Ok(Outcome::Output(output)) => writeln!(writer, "{output}")?,
+            Err(e) => writeln!(writer, "Error: {e}")?,
+        }
+        prompt(&mut writer, session.day.day)?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "day5"))]
+mod test_repl {
+    use super::{run, Outcome, Session};
+
+    const DAY_5_EXAMPLE: &str = "\
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    #[test]
+    fn execute_commands() {
+        let mut session = Session::new(5, DAY_5_EXAMPLE.to_string()).unwrap();
+        k9::assert_equal!(
+            session.execute("map seed-to-soil 79").unwrap(),
+            Outcome::Output("81".to_string())
+        );
+        k9::assert_equal!(session.execute("quit").unwrap(), Outcome::Quit);
+        assert!(session.execute("map seed-to-soil").is_err());
+        assert!(session.execute("frobnicate").is_err());
+        match session.execute("part1").unwrap() {
+            Outcome::Output(output) => assert!(output.starts_with("35 ")),
+            Outcome::Quit => panic!("Should not quit"),
+        }
+    }
+
+    #[test]
+    fn malformed_input() {
+        let mut session = Session::new(5, "seeds: 79 14\n\nnot a map".to_string()).unwrap();
+        let error = session.execute("part1").unwrap_err().to_string();
+        assert!(error.starts_with("part1 panicked: "), "{error}");
+        assert!(session.execute("part2").is_err());
+        // the session is still usable
+        assert!(session.execute("help").is_ok());
+    }
+
+    #[test]
+    fn run_script() {
+        let mut session = Session::new(5, DAY_5_EXAMPLE.to_string()).unwrap();
+        let script = "summary\n\nnope\ntrace 13\nquit\nmap seed-to-soil 1\n";
+        let mut output = Vec::<u8>::new();
+        run(&mut session, script.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        k9::assert_equal!(
+            output,
+            "\
+day5> 4 seeds: [79, 14, 55, 13]
+seed-to-soil: 2 ranges
+soil-to-fertilizer: 3 ranges
+fertilizer-to-water: 4 ranges
+water-to-light: 2 ranges
+light-to-temperature: 3 ranges
+temperature-to-humidity: 2 ranges
+humidity-to-location: 2 ranges
+day5> day5> Error: Unknown command nope, type `help` to list the commands
+day5> seed 13 -> soil 13 -> fertilizer 52 -> water 41 -> light 34 -> temperature 34 -> humidity 35 -> location 35
+day5> "
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::isolate::{self, Status};
+use crate::registry::{self, Solver};
+
+/// Limits applied to every request, so a single call can't take the service down.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub max_input_bytes: usize,
+    pub timeout: Duration,
+    /// Requests coming while this many solutions are running are turned away.
+    pub max_in_flight: usize,
+    pub worker: Worker,
+}
+
+/// The endpoints of the service.
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    /// `GET /2023/days`
+    ListDays,
+    /// `POST /2023/day/{day}/part/{part}`
+    Solve { day: u8, part: u8 },
+}
+
+fn route(method: &Method, url: &str) -> Option<Route> {
+    let path = url.split('?').next().unwrap_or(url);
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["2023", "days"]) => Some(Route::ListDays),
+        (Method::Post, ["2023", "day", day, "part", part]) => Some(Route::Solve {
+            day: day.parse().ok()?,
+            part: part.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+fn list_days() -> Value {
+    let days = registry::DAYS
+        .iter()
+        .map(|d| json!({ "day": d.day, "parts": [1, 2] }))
+        .collect::<Vec<_>>();
+    json!({ "year": 2023, "days": days })
+}
+
+/// How the service runs the solutions.
+#[derive(Debug, Clone)]
+pub enum Worker {
+    /// In a thread of the service. There is no way to kill a thread, so a solution that
+    /// times out keeps running in the background until it finishes on its own, and a stack
+    /// overflow takes the whole service down.
+    Thread,
+    /// In a child process of this executable, e.g. `std::env::current_exe()`, running its
+    /// `isolated` command. It is killed when it times out.
+    Process(PathBuf),
+}
+
+/// A solution running, counted in `running` until it's dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    /// Count one more solution running, unless there are already `max` of them.
+    fn take(running: &Arc<AtomicUsize>, max: usize) -> Option<Slot> {
+        running
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()?;
+        Some(Slot(running.clone()))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The answer as a JSON number when it is one, which all of them are so far.
+fn answer_to_json(answer: &str) -> Value {
+    if let Ok(v) = answer.parse::<i64>() {
+        json!(v)
+    } else if let Ok(v) = answer.parse::<u64>() {
+        json!(v)
+    } else {
+        json!(answer)
+    }
+}
+
+fn run(
+    limits: &Limits,
+    day: u8,
+    part: u8,
+    solver: Solver,
+    input: String,
+    slot: Slot,
+) -> Result<Status> {
+    let timeout = limits.timeout;
+    match &limits.worker {
+        Worker::Thread => {
+            let (sender, receiver) = mpsc::channel();
+            std::thread::spawn(move || {
+                // the solution is still running until it returns, even after a timeout
+                let _slot = slot;
+                let _ = sender.send(isolate::solve(solver, &input, false));
+            });
+            Ok(receiver
+                .recv_timeout(timeout)
+                .unwrap_or(Status::TimedOut(timeout)))
+        }
+        Worker::Process(exe) => {
+            let mut command = Command::new(exe);
+            command.args(["isolated", &day.to_string(), &part.to_string()]);
+            isolate::run_in_subprocess(command, &input, timeout)
+        }
+    }
+}
+
+fn solve(
+    day: u8,
+    part: u8,
+    input: String,
+    limits: &Limits,
+    running: &Arc<AtomicUsize>,
+) -> (u16, Value) {
+    let Some(solver) = registry::get_day(day).and_then(|d| d.part(part)) else {
+        return (
+            404,
+            json!({ "error": format!("No solution for day {day} part {part}") }),
+        );
+    };
+    let Some(slot) = Slot::take(running, limits.max_in_flight) else {
+        return (
+            503,
+            json!({ "error": format!("Already solving {} inputs, try again later", limits.max_in_flight) }),
+        );
+    };
+
+    let status = match run(limits, day, part, solver, input, slot) {
+        Ok(status) => status,
+        Err(e) => return (500, json!({ "error": format!("{e:#}") })),
+    };
+    match status {
+        Status::Solved {
+            answer, elapsed, ..
+        } => (
+            200,
+            json!({
+                "day": day,
+                "part": part,
+                "answer": answer_to_json(&answer),
+                "elapsed_ms": elapsed.as_secs_f64() * 1000.,
+            }),
+        ),
+        Status::TimedOut(timeout) => (
+            504,
+            json!({ "error": format!("Timed out after {:?}", timeout) }),
+        ),
+        Status::Panicked(_) => (
+            500,
+            json!({ "error": "The solution panicked, the input is probably malformed" }),
+        ),
+        Status::StackOverflow => (500, json!({ "error": "The solution overflowed its stack" })),
+        Status::Crashed(reason) => (
+            500,
+            json!({ "error": format!("The solution crashed: {reason}") }),
+        ),
+    }
+}
+
+/// Read the body of the request, refusing it if it's over the size limit.
+fn read_body(request: &mut Request, max_bytes: usize) -> Result<String, (u16, Value)> {
+    let too_large = || {
+        (
+            413,
+            json!({ "error": format!("Input is larger than {max_bytes} bytes") }),
+        )
+    };
+    if request.body_length().is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(max_bytes as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| (400, json!({ "error": format!("Invalid body: {e}") })))?;
+    if body.len() > max_bytes {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+fn handle(mut request: Request, limits: &Limits, running: &Arc<AtomicUsize>) {
+    let (status, body) = match route(request.method(), request.url()) {
+        Some(Route::ListDays) => (200, list_days()),
+        Some(Route::Solve { day, part }) => match read_body(&mut request, limits.max_input_bytes) {
+            Ok(input) => solve(day, part, input, limits, running),
+            Err(error) => error,
+        },
+        None => (404, json!({ "error": "Not found" })),
+    };
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    let _ = request.respond(response);
+}
+
+/// Start the service on `addr`, e.g. `127.0.0.1:8023`. Port 0 picks any free port.
+pub fn bind(addr: &str) -> Result<Server> {
+    Server::http(addr).map_err(|e| anyhow!("Error binding to {addr}: {e}"))
+}
+
+/// Handle requests forever, each in its own thread.
+pub fn serve(server: Server, limits: Limits) {
+    let limits = Arc::new(limits);
+    let running = Arc::new(AtomicUsize::new(0));
+    for request in server.incoming_requests() {
+        let (limits, running) = (limits.clone(), running.clone());
+        std::thread::spawn(move || handle(request, &limits, &running));
+    }
+}
+
+#[cfg(test)]
+mod test_serve {
+    use super::{route, Route, Slot};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tiny_http::Method;
+
+    #[test]
+    fn routes() {
+        k9::assert_equal!(route(&Method::Get, "/2023/days"), Some(Route::ListDays));
+        k9::assert_equal!(
+            route(&Method::Post, "/2023/day/5/part/2"),
+            Some(Route::Solve { day: 5, part: 2 })
+        );
+        k9::assert_equal!(route(&Method::Get, "/2023/day/5/part/2"), None);
+        k9::assert_equal!(route(&Method::Post, "/2023/day/x/part/2"), None);
+        k9::assert_equal!(route(&Method::Post, "/2022/day/5/part/2"), None);
+    }
+
+    #[test]
+    fn slots() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let first = Slot::take(&running, 2).unwrap();
+        let second = Slot::take(&running, 2).unwrap();
+        assert!(Slot::take(&running, 2).is_none());
+        drop(first);
+        k9::assert_equal!(running.load(Ordering::SeqCst), 1);
+        assert!(Slot::take(&running, 2).is_some());
+        drop(second);
+        k9::assert_equal!(running.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    #[cfg(all(feature = "day1", feature = "day9"))]
+    fn serve_requests() {
+        use super::{bind, serve, Limits, Worker};
+        use serde_json::{json, Value};
+        use std::time::Duration;
+
+        let server = bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let limits = Limits {
+            max_input_bytes: 100,
+            timeout: Duration::from_secs(5),
+            max_in_flight: 4,
+            worker: Worker::Thread,
+        };
+        std::thread::spawn(move || serve(server, limits));
+        let client = reqwest::blocking::Client::new();
+
+        let days: Value = client
+            .get(format!("{base}/2023/days"))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        k9::assert_equal!(days["days"][0], json!({ "day": 1, "parts": [1, 2] }));
+
+        let response = client
+            .post(format!("{base}/2023/day/9/part/1"))
+            .body("0 3 6 9 12 15\n1 3 6 10 15 21\n10 13 16 21 30 45\n")
+            .send()
+            .unwrap();
+        k9::assert_equal!(response.status().as_u16(), 200);
+        let solved: Value = response.json().unwrap();
+        k9::assert_equal!(solved["answer"], json!(114));
+        assert!(solved["elapsed_ms"].is_f64());
+
+        let response = client
+            .post(format!("{base}/2023/day/9/part/1"))
+            .body("1 2 3\n".repeat(20))
+            .send()
+            .unwrap();
+        k9::assert_equal!(response.status().as_u16(), 413);
+
+        let response = client
+            .post(format!("{base}/2023/day/24/part/1"))
+            .body("")
+            .send()
+            .unwrap();
+        k9::assert_equal!(response.status().as_u16(), 404);
+
+        let response = client
+            .post(format!("{base}/2023/day/9/part/1"))
+            .body("not numbers")
+            .send()
+            .unwrap();
+        k9::assert_equal!(response.status().as_u16(), 500);
+    }
+}
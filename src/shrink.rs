@@ -0,0 +1,255 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use tracing::debug;
+
+use crate::registry::{Day, Sections};
+
+/// An input split along its structure: sections of lines, which are removed
+/// section by section, then line by line, then character by character.
+/// The values of a `label: values` first line are removed group by group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pieces {
+    sections: Vec<Vec<String>>,
+    trailing_newline: bool,
+}
+
+impl Pieces {
+    fn split(input: &str, layout: Sections) -> Pieces {
+        let trimmed = input.trim_end_matches('\n');
+        let sections = match layout {
+            Sections::None => vec![trimmed],
+            Sections::Fixed { .. } | Sections::FixedWithValues { .. } | Sections::Removable => {
+                trimmed.split("\n\n").collect()
+            }
+        };
+        Pieces {
+            sections: sections
+                .into_iter()
+                .map(|s| s.split('\n').map(String::from).collect())
+                .collect(),
+            trailing_newline: input.ends_with('\n'),
+        }
+    }
+
+    fn join(&self) -> String {
+        let mut res = self
+            .sections
+            .iter()
+            .map(|lines| lines.join("\n"))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        if self.trailing_newline {
+            res.push('\n');
+        }
+        res
+    }
+}
+
+/// Remove as many `items` as possible while `fails` still holds: try removing chunks of
+/// half of the items, then smaller and smaller chunks down to single items.
+fn ddmin<T: Clone>(mut items: Vec<T>, mut fails: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    let mut chunk = items.len().div_ceil(2);
+    while chunk > 0 && !items.is_empty() {
+        let mut removed = false;
+        let mut start = 0;
+        while start < items.len() {
+            let end = (start + chunk).min(items.len());
+            let candidate = [&items[..start], &items[end..]].concat();
+            if fails(&candidate) {
+                items = candidate;
+                removed = true;
+            } else {
+                start = end;
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    items
+}
+
+/// Remove as many groups of `group` values as possible from the `label: values` first line.
+fn shrink_values(pieces: &Pieces, group: usize, fails: &mut impl FnMut(&str) -> bool) -> Pieces {
+    let Some((label, values)) = pieces.sections[0][0].split_once(':') else {
+        return pieces.clone();
+    };
+    let values = values
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let with_groups = |groups: &[Vec<String>]| {
+        let mut candidate = pieces.clone();
+        let values = groups
+            .concat()
+            .iter()
+            .map(|v| format!(" {v}"))
+            .collect::<String>();
+        candidate.sections[0][0] = format!("{label}:{values}");
+        candidate
+    };
+    let groups = ddmin(
+        values.chunks(group).map(<[String]>::to_vec).collect(),
+        |groups| fails(&with_groups(groups).join()),
+    );
+    with_groups(&groups)
+}
+
+/// Shrink `input` to a smaller one that still `fails`, following the sections given by
+/// `layout`. The input itself must fail. The result is minimal in the sense that removing any
+/// single section, line or character from it makes the failure go away.
+pub fn shrink(input: &str, layout: Sections, mut fails: impl FnMut(&str) -> bool) -> String {
+    let mut pieces = Pieces::split(input, layout);
+    let headers = match layout {
+        Sections::Fixed { headers } | Sections::FixedWithValues { headers, .. } => headers,
+        Sections::None | Sections::Removable => 0,
+    };
+    loop {
+        let before = pieces.clone();
+
+        if layout == Sections::Removable {
+            let sections = ddmin(pieces.sections.clone(), |sections| {
+                fails(
+                    &Pieces {
+                        sections: sections.to_vec(),
+                        ..pieces.clone()
+                    }
+                    .join(),
+                )
+            });
+            pieces.sections = sections;
+        }
+
+        if let Sections::FixedWithValues { group, .. } = layout {
+            pieces = shrink_values(&pieces, group, &mut fails);
+        }
+
+        for i in 0..pieces.sections.len() {
+            let kept = headers.min(pieces.sections[i].len());
+            let lines = ddmin(pieces.sections[i][kept..].to_vec(), |lines| {
+                let mut candidate = pieces.clone();
+                candidate.sections[i].truncate(kept);
+                candidate.sections[i].extend_from_slice(lines);
+                fails(&candidate.join())
+            });
+            pieces.sections[i].truncate(kept);
+            pieces.sections[i].extend(lines);
+        }
+
+        for i in 0..pieces.sections.len() {
+            let kept = headers.min(pieces.sections[i].len());
+            for j in kept..pieces.sections[i].len() {
+                let chars = ddmin(pieces.sections[i][j].chars().collect(), |chars| {
+                    let mut candidate = pieces.clone();
+                    candidate.sections[i][j] = chars.iter().collect();
+                    fails(&candidate.join())
+                });
+                pieces.sections[i][j] = chars.into_iter().collect();
+            }
+        }
+
+        debug!(bytes = pieces.join().len(), "shrink pass done");
+        // removing characters can make more lines removable, so go on until nothing changes
+        if pieces == before {
+            return pieces.join();
+        }
+    }
+}
+
+/// A predicate for [`shrink`]: all the implementations of `part` of `day` give an answer,
+/// but not the same one. Inputs that make one of them panic are not what we're after,
+/// so they don't count.
+pub fn disagree(day: &Day, part: u8) -> impl FnMut(&str) -> bool + '_ {
+    let implementations = day.implementations(part);
+    move |input| {
+        let answers = implementations
+            .iter()
+            .map(|(_, solve)| panic::catch_unwind(AssertUnwindSafe(|| solve(input).to_string())))
+            .collect::<Result<Vec<_>, _>>();
+        match answers {
+            Ok(answers) => answers.iter().any(|a| a != &answers[0]),
+            Err(_) => false,
+        }
+    }
+}
+
+/// A predicate for [`shrink`]: the main solution of `part` of `day` panics.
+pub fn panics(day: &Day, part: u8) -> impl FnMut(&str) -> bool {
+    let solve = day.part(part);
+    move |input| {
+        solve.is_some_and(|solve| panic::catch_unwind(AssertUnwindSafe(|| solve(input))).is_err())
+    }
+}
+
+#[cfg(test)]
+mod test_shrink {
+    use super::shrink;
+    use crate::registry::Sections;
+
+    #[test]
+    fn shrink_lines_and_chars() {
+        let input = (0..100).map(|i| format!("{i}\n")).collect::<String>();
+        let res = shrink(&input, Sections::None, |input| {
+            input.lines().any(|l| l.contains("42")) && input.contains('7')
+        });
+        k9::assert_equal!(res, "7\n42\n");
+    }
+
+    #[test]
+    #[cfg(feature = "day5")]
+    fn keep_day5_headers() {
+        let input = crate::registry::get_day(5).unwrap().generate_input(0, 8);
+        let res = shrink(&input, Sections::Fixed { headers: 1 }, |input| {
+            input.contains("map:\n1 ")
+        });
+        k9::assert_equal!(res.matches("map:").count(), 7);
+        k9::assert_equal!(res.split("\n\n").count(), 8);
+        assert!(res.len() < input.len() / 2);
+    }
+
+    #[test]
+    fn remove_value_groups() {
+        let input = "seeds: 1 2 3 4 5 6\n\nx map:\n1 2 3\n\ny map:\n4 5 6\n";
+        let layout = Sections::FixedWithValues {
+            group: 2,
+            headers: 1,
+        };
+        let res = shrink(input, layout, |input| input.contains(" 3 4"));
+        k9::assert_equal!(res, "seeds: 3 4\n\nx map:\n\ny map:\n");
+        let res = shrink(input, layout, |input| input.contains("6\n"));
+        k9::assert_equal!(res, "seeds:\n\nx map:\n\ny map:\n6\n");
+    }
+
+    #[test]
+    fn remove_sections() {
+        let input = "#.\n..\n\n##\n..\n\n.#\n#.\n";
+        let res = shrink(input, Sections::Removable, |input| input.contains("##"));
+        k9::assert_equal!(res, "##\n");
+    }
+
+    #[test]
+    #[cfg(feature = "day9")]
+    fn shrink_disagreement() {
+        use super::disagree;
+        use crate::registry::{get_day, Day, Implementation};
+
+        let day = Day {
+            alternatives: &[Implementation {
+                name: "skip_negative",
+                part: 1,
+                solve: |input| {
+                    let kept = input
+                        .lines()
+                        .filter(|l| !l.contains('-'))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    crate::puzzles::day9::part1(&kept).into()
+                },
+            }],
+            ..*get_day(9).unwrap()
+        };
+        let input = "1 2 3\n3 -1 4\n5 6 7 8\n";
+        let res = shrink(input, day.sections, disagree(&day, 1));
+        k9::assert_equal!(res, "-1\n");
+    }
+}
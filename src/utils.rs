@@ -1,3 +1,13 @@
+pub mod bitgrid;
+pub mod cycle;
+pub mod geom;
+pub mod grid;
+pub mod intervals;
+pub mod numtheory;
+pub mod parse;
+pub mod polygon;
+pub mod search;
+
 /// Transpose a vec of vecs
 pub fn transpose<T>(v: Vec<Vec<T>>) -> Vec<Vec<T>> {
     assert!(!v.is_empty());
@@ -21,4 +31,34 @@ pub fn flip<T>(v: Vec<Vec<T>>) -> Vec<Vec<T>> {
         .collect()
 }
 
-use std;
+use rand::Rng;
+
+/// A random grid of `rows` x `cols`, each char picked with the given relative weight.
+pub fn random_grid(
+    rng: &mut impl Rng,
+    rows: usize,
+    cols: usize,
+    weights: &[(char, u32)],
+) -> Vec<Vec<char>> {
+    let total = weights.iter().map(|(_, w)| w).sum::<u32>();
+    let mut pick = || {
+        let mut n = rng.gen_range(0..total);
+        for &(c, w) in weights {
+            if n < w {
+                return c;
+            }
+            n -= w;
+        }
+        unreachable!("n is smaller than the total weight")
+    };
+    (0..rows)
+        .map(|_| (0..cols).map(|_| pick()).collect())
+        .collect()
+}
+
+/// Render a grid as lines of text.
+pub fn grid_to_string(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
@@ -0,0 +1,547 @@
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+use super::geom::Direction;
+use super::grid::{Grid, Pos};
+
+/// A rectangular grid of booleans with one bit per cell, e.g. where the rocks are, or which
+/// cells were visited. Each row is stored in its own `u64` words, column `c` being bit
+/// `c % 64` of word `c / 64`, so whole rows can be combined, shifted, counted, flipped and
+/// transposed a word at a time. The bits past the last column are always 0.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    words: Vec<u64>,
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+}
+
+/// Move the bits of a row `n` places towards the higher ones, dropping those falling off.
+fn shift_up(words: &mut [u64], n: usize) {
+    let (skip, bits) = (n / 64, n % 64);
+    // from the top, so that the words read are not overwritten yet
+    for i in (0..words.len()).rev() {
+        let low = i.checked_sub(skip).map_or(0, |j| words[j]);
+        let carry = i.checked_sub(skip + 1).map_or(0, |j| words[j]);
+        words[i] = if bits == 0 {
+            low
+        } else {
+            low << bits | carry >> (64 - bits)
+        };
+    }
+}
+
+/// Move the bits of a row `n` places towards the lower ones, dropping those falling off.
+fn shift_down(words: &mut [u64], n: usize) {
+    let (skip, bits) = (n / 64, n % 64);
+    for i in 0..words.len() {
+        let high = words.get(i + skip).copied().unwrap_or(0);
+        let carry = words.get(i + skip + 1).copied().unwrap_or(0);
+        words[i] = if bits == 0 {
+            high
+        } else {
+            high >> bits | carry << (64 - bits)
+        };
+    }
+}
+
+/// Transpose a 64 x 64 block of bits, bit `c` of word `r` going to bit `r` of word `c`: swap
+/// the top right and bottom left 32 x 32 quarters, then do the same in each quarter, and so
+/// on down to single bits, each step working on all the words at once.
+fn transpose_block(block: &mut [u64; 64]) {
+    let mut size = 32;
+    // the low `size` bits of each group of `2 * size` bits
+    let mut mask: u64 = 0x0000_0000_ffff_ffff;
+    while size > 0 {
+        // the rows of the top halves of the quarters, whose bottom halves are `size` rows down
+        let mut row = 0;
+        while row < 64 {
+            let swapped = ((block[row] >> size) ^ block[row + size]) & mask;
+            block[row] ^= swapped << size;
+            block[row + size] ^= swapped;
+            row = (row + size + 1) & !size;
+        }
+        size /= 2;
+        mask ^= mask << size;
+    }
+}
+
+impl BitGrid {
+    /// A `width` x `height` grid with all the cells unset.
+    pub fn new(width: usize, height: usize) -> BitGrid {
+        let words_per_row = width.div_ceil(64);
+        BitGrid {
+            words: vec![0; words_per_row * height],
+            width,
+            height,
+            words_per_row,
+        }
+    }
+
+    /// A `width` x `height` grid with the cells for which `f` is true set.
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos) -> bool) -> BitGrid {
+        let mut grid = BitGrid::new(width, height);
+        for row in 0..height {
+            for col in 0..width {
+                if f((row, col)) {
+                    grid.insert((row, col));
+                }
+            }
+        }
+        grid
+    }
+
+    /// The cells of `grid` for which `f` is true, e.g. the rocks of a parsed input.
+    pub fn from_grid<T>(grid: &Grid<T>, mut f: impl FnMut(&T) -> bool) -> BitGrid {
+        BitGrid::from_fn(grid.width(), grid.height(), |pos| f(&grid[pos]))
+    }
+
+    pub fn to_grid(&self) -> Grid<bool> {
+        let mut grid = Grid::new(self.width, self.height, false);
+        for pos in self.iter_ones() {
+            grid[pos] = true;
+        }
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Where the bit of a cell is, as the index of its word and a mask for it in the word.
+    fn bit(&self, (row, col): Pos) -> (usize, u64) {
+        assert!(
+            row < self.height && col < self.width,
+            "({row}, {col}) is out of a {}x{} grid",
+            self.width,
+            self.height
+        );
+        (row * self.words_per_row + col / 64, 1 << (col % 64))
+    }
+
+    /// Whether the cell is set, which cells outside of the grid never are.
+    pub fn get(&self, (row, col): Pos) -> bool {
+        if row >= self.height || col >= self.width {
+            return false;
+        }
+        let (word, mask) = self.bit((row, col));
+        self.words[word] & mask != 0
+    }
+
+    pub fn set(&mut self, pos: Pos, value: bool) {
+        let (word, mask) = self.bit(pos);
+        if value {
+            self.words[word] |= mask;
+        } else {
+            self.words[word] &= !mask;
+        }
+    }
+
+    /// Set the cell, returning whether it wasn't already, like `HashSet::insert`.
+    pub fn insert(&mut self, pos: Pos) -> bool {
+        let (word, mask) = self.bit(pos);
+        let new = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        new
+    }
+
+    /// Unset the cell, returning whether it was set, like `HashSet::remove`.
+    pub fn remove(&mut self, pos: Pos) -> bool {
+        let (word, mask) = self.bit(pos);
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] &= !mask;
+        was_set
+    }
+
+    /// The words of a row, lowest columns first.
+    pub fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    /// The words of a row, lowest columns first. The bits past the last column must be
+    /// left unset.
+    pub fn row_mut(&mut self, row: usize) -> &mut [u64] {
+        &mut self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    /// The bits of the last word of each row that are actual columns.
+    fn last_word_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => u64::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    /// Unset the bits past the last column, after an operation that may have set them.
+    fn clear_padding(&mut self) {
+        let mask = self.last_word_mask();
+        if let Some(last) = self.words_per_row.checked_sub(1) {
+            for row in 0..self.height {
+                self.row_mut(row)[last] &= mask;
+            }
+        }
+    }
+
+    /// Number of cells set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Number of cells set in a row.
+    pub fn count_ones_in_row(&self, row: usize) -> usize {
+        self.row(row).iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// The cells that are set, row by row.
+    pub fn iter_ones(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.words.iter().enumerate().flat_map(move |(i, &word)| {
+            let (row, first_col) = (i / self.words_per_row, i % self.words_per_row * 64);
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                (rest != 0).then(|| {
+                    let bit = rest.trailing_zeros() as usize;
+                    // clear the lowest bit set
+                    rest &= rest - 1;
+                    (row, first_col + bit)
+                })
+            })
+        })
+    }
+
+    /// Move every cell `n` cells towards `direction`, dropping the ones that fall off the
+    /// grid and leaving the cells behind them unset.
+    pub fn shift(&mut self, direction: Direction, n: usize) {
+        let (height, words_per_row) = (self.height, self.words_per_row);
+        let n_words = n.min(height) * words_per_row;
+        match direction {
+            Direction::North => {
+                self.words.copy_within(n_words.., 0);
+                let len = self.words.len();
+                self.words[len - n_words..].fill(0);
+            }
+            Direction::South => {
+                let len = self.words.len();
+                self.words.copy_within(..len - n_words, n_words);
+                self.words[..n_words].fill(0);
+            }
+            Direction::East => {
+                for row in 0..height {
+                    shift_up(self.row_mut(row), n);
+                }
+                self.clear_padding();
+            }
+            Direction::West => {
+                for row in 0..height {
+                    shift_down(self.row_mut(row), n);
+                }
+            }
+        }
+    }
+
+    /// Swap rows and columns, 64 x 64 cells at a time.
+    pub fn transpose(&mut self) {
+        let mut res = BitGrid::new(self.height, self.width);
+        let mut block = [0; 64];
+        for block_row in 0..self.height.div_ceil(64) {
+            for block_col in 0..self.words_per_row {
+                // the rows past the last one are empty, like the padding past the last column
+                for (i, word) in block.iter_mut().enumerate() {
+                    let row = block_row * 64 + i;
+                    *word = if row < self.height {
+                        self.row(row)[block_col]
+                    } else {
+                        0
+                    };
+                }
+                transpose_block(&mut block);
+                for (i, &word) in block.iter().enumerate() {
+                    let row = block_col * 64 + i;
+                    if row < res.height {
+                        res.row_mut(row)[block_row] = word;
+                    }
+                }
+            }
+        }
+        *self = res;
+    }
+
+    /// Mirror the grid left to right.
+    pub fn flip_horizontal(&mut self) {
+        let padding = self.words_per_row * 64 - self.width;
+        for row in 0..self.height {
+            let words = self.row_mut(row);
+            words.reverse();
+            for word in words.iter_mut() {
+                *word = word.reverse_bits();
+            }
+            // the padding is now before the first column
+            shift_down(words, padding);
+        }
+    }
+
+    /// Mirror the grid top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let words_per_row = self.words_per_row;
+        for row in 0..self.height / 2 {
+            let mirror = self.height - 1 - row;
+            let (top, bottom) = self.words.split_at_mut(mirror * words_per_row);
+            top[row * words_per_row..(row + 1) * words_per_row]
+                .swap_with_slice(&mut bottom[..words_per_row]);
+        }
+    }
+
+    /// Rotate the grid a quarter turn clockwise.
+    pub fn rotate_clockwise(&mut self) {
+        self.transpose();
+        self.flip_horizontal();
+    }
+
+    /// Rotate the grid a quarter turn counterclockwise.
+    pub fn rotate_counterclockwise(&mut self) {
+        self.transpose();
+        self.flip_vertical();
+    }
+
+    /// Combine the words of two grids of the same size.
+    fn combine(&mut self, other: &BitGrid, f: impl Fn(u64, u64) -> u64) {
+        assert!(
+            (self.width, self.height) == (other.width, other.height),
+            "combining a {}x{} grid with a {}x{} one",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word = f(*word, *other);
+        }
+    }
+}
+
+impl BitOrAssign<&BitGrid> for BitGrid {
+    fn bitor_assign(&mut self, other: &BitGrid) {
+        self.combine(other, |a, b| a | b);
+    }
+}
+
+impl BitAndAssign<&BitGrid> for BitGrid {
+    fn bitand_assign(&mut self, other: &BitGrid) {
+        self.combine(other, |a, b| a & b);
+    }
+}
+
+impl BitXorAssign<&BitGrid> for BitGrid {
+    fn bitxor_assign(&mut self, other: &BitGrid) {
+        self.combine(other, |a, b| a ^ b);
+    }
+}
+
+impl BitOr for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitor(self, other: &BitGrid) -> BitGrid {
+        let mut res = self.clone();
+        res |= other;
+        res
+    }
+}
+
+impl BitAnd for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitand(self, other: &BitGrid) -> BitGrid {
+        let mut res = self.clone();
+        res &= other;
+        res
+    }
+}
+
+impl BitXor for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitxor(self, other: &BitGrid) -> BitGrid {
+        let mut res = self.clone();
+        res ^= other;
+        res
+    }
+}
+
+/// The cells that are not set.
+impl Not for &BitGrid {
+    type Output = BitGrid;
+
+    fn not(self) -> BitGrid {
+        let mut res = self.clone();
+        for word in &mut res.words {
+            *word = !*word;
+        }
+        res.clear_padding();
+        res
+    }
+}
+
+/// `#` for the cells that are set and `.` for the others.
+impl FromStr for BitGrid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let grid = Grid::parse_with(s, |c| match c {
+            '#' => Ok(true),
+            '.' => Ok(false),
+            _ => bail!("Expected `#` or `.`, found `{c}`"),
+        })?;
+        Ok(BitGrid::from_grid(&grid, |cell| *cell))
+    }
+}
+
+/// `#` for the cells that are set and `.` for the others, one line per row.
+impl fmt::Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.height {
+            for col in 0..self.width {
+                write!(f, "{}", if self.get((row, col)) { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_bitgrid {
+    use super::BitGrid;
+    use crate::utils::geom::Direction;
+    use crate::utils::grid::Grid;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const SHAPE: &str = "\
+#..
+##.
+";
+
+    fn random_grid(rng: &mut StdRng) -> Grid<bool> {
+        // big enough to need a few words per row, and a few blocks once transposed
+        let (width, height) = (rng.gen_range(1..150), rng.gen_range(1..150));
+        let mut grid = Grid::new(width, height, false);
+        for (_, cell) in grid.iter_mut() {
+            *cell = rng.gen_bool(0.3);
+        }
+        grid
+    }
+
+    #[test]
+    fn cells() {
+        let mut grid = SHAPE.parse::<BitGrid>().unwrap();
+        k9::assert_equal!(grid.count_ones(), 3);
+        assert!(grid.get((1, 1)) && !grid.get((0, 1)) && !grid.get((5, 5)));
+        assert!(grid.insert((0, 2)));
+        assert!(!grid.insert((0, 2)));
+        assert!(grid.remove((0, 0)));
+        k9::assert_equal!(
+            grid.iter_ones().collect::<Vec<_>>(),
+            [(0, 2), (1, 0), (1, 1)]
+        );
+        k9::assert_equal!(grid.to_string(), "..#\n##.\n");
+        k9::assert_equal!(grid.count_ones_in_row(1), 2);
+    }
+
+    #[test]
+    fn transformations() {
+        let mut grid = SHAPE.parse::<BitGrid>().unwrap();
+        grid.rotate_clockwise();
+        k9::assert_equal!(grid.to_string(), "##\n#.\n..\n");
+        grid.rotate_counterclockwise();
+        grid.transpose();
+        k9::assert_equal!(grid.to_string(), "##\n.#\n..\n");
+        grid.flip_horizontal();
+        k9::assert_equal!(grid.to_string(), "##\n#.\n..\n");
+        grid.flip_vertical();
+        k9::assert_equal!(grid.to_string(), "..\n#.\n##\n");
+    }
+
+    #[test]
+    fn matches_grid_of_bools() {
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let cells = random_grid(&mut rng);
+            let grid = BitGrid::from_grid(&cells, |cell| *cell);
+            k9::assert_equal!(grid.to_grid(), cells);
+            k9::assert_equal!(grid.count_ones(), cells.positions(|c| *c).count());
+            k9::assert_equal!(
+                grid.iter_ones().collect::<Vec<_>>(),
+                cells.positions(|c| *c).collect::<Vec<_>>()
+            );
+
+            type Transformation = (fn(&mut BitGrid), fn(&mut Grid<bool>));
+            let transformations: [Transformation; 5] = [
+                (BitGrid::transpose, Grid::transpose),
+                (BitGrid::flip_horizontal, Grid::flip_horizontal),
+                (BitGrid::flip_vertical, Grid::flip_vertical),
+                (BitGrid::rotate_clockwise, Grid::rotate_clockwise),
+                (
+                    BitGrid::rotate_counterclockwise,
+                    Grid::rotate_counterclockwise,
+                ),
+            ];
+            for (transform_bits, transform_cells) in transformations {
+                let (mut grid, mut cells) = (grid.clone(), cells.clone());
+                transform_bits(&mut grid);
+                transform_cells(&mut cells);
+                // with the padding unset, as it's compared too
+                k9::assert_equal!(grid, BitGrid::from_grid(&cells, |cell| *cell));
+            }
+
+            let n = rng.gen_range(0..150);
+            for direction in Direction::iter() {
+                let mut shifted = grid.clone();
+                shifted.shift(direction, n);
+                let offset = direction.offset() * n as i64;
+                let expected = BitGrid::from_fn(cells.width(), cells.height(), |(row, col)| {
+                    let (row, col) = (row as i64 - offset.dy, col as i64 - offset.dx);
+                    row >= 0 && col >= 0 && grid.get((row as usize, col as usize))
+                });
+                k9::assert_equal!(shifted, expected, "{direction:?} {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn masks() {
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let a = random_grid(&mut rng);
+            let mut b = a.clone();
+            for (_, cell) in b.iter_mut() {
+                *cell = rng.gen_bool(0.5);
+            }
+            let (bits_a, bits_b) = (
+                BitGrid::from_grid(&a, |c| *c),
+                BitGrid::from_grid(&b, |c| *c),
+            );
+            let cellwise = |f: fn(bool, bool) -> bool| {
+                BitGrid::from_fn(a.width(), a.height(), |pos| f(a[pos], b[pos]))
+            };
+            k9::assert_equal!(&bits_a | &bits_b, cellwise(|x, y| x | y));
+            k9::assert_equal!(&bits_a & &bits_b, cellwise(|x, y| x & y));
+            k9::assert_equal!(&bits_a ^ &bits_b, cellwise(|x, y| x ^ y));
+            let not_a = !&bits_a;
+            k9::assert_equal!(not_a, cellwise(|x, _| !x));
+            k9::assert_equal!(
+                not_a.count_ones() + bits_a.count_ones(),
+                a.width() * a.height()
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Where the states of a simulation start repeating: state `start + period` is state `start`
+/// again, and so on forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// Number of steps before entering the cycle, i.e. the first state in it.
+    pub start: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// The earliest step whose state is the same as the one after `n` steps.
+    pub fn reduce(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.period
+        }
+    }
+}
+
+/// A 64 bit hash of `state`, standing in for it when states are too big to keep around.
+/// Different states can collide, which is unlikely enough to be ignored for puzzles.
+pub fn fingerprint<S: Hash>(state: &S) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Floyd's tortoise and hare: find the cycle of the states reached from `initial` by
+/// repeatedly calling `step`, keeping only two states around. The states must eventually
+/// repeat, otherwise this never returns.
+pub fn floyd<S: Eq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        let next = step(&hare);
+        hare = step(&next);
+    }
+
+    // the hare is now a multiple of the period ahead, so both meet at the start of the cycle
+    let mut start = 0;
+    tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut period = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        period += 1;
+    }
+    Cycle { start, period }
+}
+
+/// Brent's algorithm: same as [`floyd`], with fewer calls to `step`.
+pub fn brent<S: Clone + Eq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    // look for the period in windows of doubling sizes
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    // start again with the hare a period ahead, so both meet at the start of the cycle
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, period }
+}
+
+/// Find the cycle of the states reached from `initial` by repeatedly calling `step`,
+/// remembering the step at which each [`fingerprint`] was first seen. This takes a single
+/// pass, which is best when `step` is expensive.
+pub fn find<S: Hash>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut seen = HashMap::new();
+    let mut state = initial;
+    for n in 0.. {
+        if let Some(start) = seen.insert(fingerprint(&state), n) {
+            return Cycle {
+                start,
+                period: n - start,
+            };
+        }
+        state = step(&state);
+    }
+    unreachable!()
+}
+
+/// The state after `n` calls to `step` from `initial`, skipping the whole repetitions of
+/// the cycle once it's found, e.g. to simulate a billion steps of a periodic process.
+pub fn fast_forward<S: Hash>(initial: S, mut step: impl FnMut(&S) -> S, n: usize) -> S {
+    let mut seen = HashMap::new();
+    let mut state = initial;
+    let mut i = 0;
+    while i < n {
+        if let Some(start) = seen.insert(fingerprint(&state), i) {
+            let period = i - start;
+            // we're in the cycle, so only the steps past its last repetition remain
+            for _ in 0..(n - i) % period {
+                state = step(&state);
+            }
+            return state;
+        }
+        state = step(&state);
+        i += 1;
+    }
+    state
+}
+
+#[cfg(test)]
+mod test_cycle {
+    use super::{brent, fast_forward, find, floyd, Cycle};
+
+    /// 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 6 -> 3 -> ...
+    fn rho(n: &u32) -> u32 {
+        if *n == 6 {
+            3
+        } else {
+            n + 1
+        }
+    }
+
+    #[test]
+    fn every_method_finds_the_cycle() {
+        let expected = Cycle {
+            start: 3,
+            period: 4,
+        };
+        k9::assert_equal!(floyd(0, rho), expected);
+        k9::assert_equal!(brent(0, rho), expected);
+        k9::assert_equal!(find(0, rho), expected);
+
+        // already in the cycle
+        let expected = Cycle {
+            start: 0,
+            period: 4,
+        };
+        k9::assert_equal!(floyd(5, rho), expected);
+        k9::assert_equal!(brent(5, rho), expected);
+        k9::assert_equal!(find(5, rho), expected);
+    }
+
+    #[test]
+    fn fixed_point() {
+        let expected = Cycle {
+            start: 2,
+            period: 1,
+        };
+        let step = |n: &u32| (n + 1).min(2);
+        k9::assert_equal!(floyd(0, step), expected);
+        k9::assert_equal!(brent(0, step), expected);
+        k9::assert_equal!(find(0, step), expected);
+    }
+
+    #[test]
+    fn long_cycle() {
+        // the map of Pollard's rho, which wanders a while before looping
+        let step = |n: &u64| (n * n + 1) % 10007;
+        let cycle = brent(0, step);
+        assert!(cycle.start > 0 && cycle.period > 1);
+        k9::assert_equal!(floyd(0, step), cycle);
+        k9::assert_equal!(find(0, step), cycle);
+        let mut state = 0;
+        for _ in 0..cycle.start {
+            state = step(&state);
+        }
+        let entry = state;
+        for _ in 0..cycle.period {
+            state = step(&state);
+        }
+        k9::assert_equal!(state, entry);
+    }
+
+    #[test]
+    fn reduce_and_fast_forward() {
+        let cycle = find(0, rho);
+        k9::assert_equal!(cycle.reduce(2), 2);
+        k9::assert_equal!(cycle.reduce(7), 3);
+        k9::assert_equal!(cycle.reduce(1_000_000_001), 5);
+
+        for n in [0, 2, 3, 7, 10, 1_000_000_000, 1_000_000_001] {
+            let mut state = 0;
+            for _ in 0..cycle.reduce(n) {
+                state = rho(&state);
+            }
+            k9::assert_equal!(fast_forward(0, rho, n), state);
+        }
+    }
+}
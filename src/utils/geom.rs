@@ -0,0 +1,315 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use super::grid::Pos;
+
+/// A point on the plane, with `y` growing downwards like the rows of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// The difference between two points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Vector {
+    pub dx: i64,
+    pub dy: i64,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    /// The point at the `(row, col)` position of a grid.
+    pub fn from_pos((row, col): Pos) -> Point {
+        Point::new(col as i64, row as i64)
+    }
+
+    /// The `(row, col)` position of the point in a grid, if it's not negative.
+    pub fn to_pos(self) -> Option<Pos> {
+        Some((self.y.try_into().ok()?, self.x.try_into().ok()?))
+    }
+
+    /// The point one step away in `direction`.
+    pub fn step(self, direction: Direction) -> Point {
+        self + direction.offset()
+    }
+
+    /// The point one step away in `direction`, if it's still in a `width` x `height`
+    /// rectangle starting at the origin.
+    pub fn step_within(self, direction: Direction, width: usize, height: usize) -> Option<Point> {
+        self.offset_within(direction.offset(), width, height)
+    }
+
+    /// The point `vector` away, if it's still in a `width` x `height` rectangle starting
+    /// at the origin.
+    pub fn offset_within(self, vector: Vector, width: usize, height: usize) -> Option<Point> {
+        let x = self.x.checked_add(vector.dx)?;
+        let y = self.y.checked_add(vector.dy)?;
+        let inside = (0..width as i64).contains(&x) && (0..height as i64).contains(&y);
+        inside.then_some(Point::new(x, y))
+    }
+
+    /// The 4 points sharing a side with this one, clockwise from the one above.
+    pub fn neighbours4(self) -> impl Iterator<Item = Point> {
+        Direction::iter().map(move |d| self.step(d))
+    }
+
+    /// The 8 points around this one, clockwise from the top left one.
+    pub fn neighbours8(self) -> impl Iterator<Item = Point> {
+        Vector::AROUND.into_iter().map(move |v| self + v)
+    }
+
+    /// Distance when moving only horizontally or vertically.
+    pub fn manhattan(self, other: Point) -> i64 {
+        (other - self).manhattan()
+    }
+
+    /// Distance when diagonal moves are allowed too, the way a king moves in chess.
+    pub fn chebyshev(self, other: Point) -> i64 {
+        (other - self).chebyshev()
+    }
+}
+
+impl Vector {
+    /// The offsets to the 8 points around another one, clockwise from the top left one.
+    pub const AROUND: [Vector; 8] = [
+        Vector::new(-1, -1),
+        Vector::new(0, -1),
+        Vector::new(1, -1),
+        Vector::new(1, 0),
+        Vector::new(1, 1),
+        Vector::new(0, 1),
+        Vector::new(-1, 1),
+        Vector::new(-1, 0),
+    ];
+
+    pub const fn new(dx: i64, dy: i64) -> Vector {
+        Vector { dx, dy }
+    }
+
+    pub fn manhattan(self) -> i64 {
+        self.dx.abs() + self.dy.abs()
+    }
+
+    pub fn chebyshev(self) -> i64 {
+        self.dx.abs().max(self.dy.abs())
+    }
+
+    /// The vector turned a quarter turn clockwise, as seen with `y` growing downwards.
+    pub fn turn_right(self) -> Vector {
+        Vector::new(-self.dy, self.dx)
+    }
+
+    /// The vector turned a quarter turn counterclockwise, as seen with `y` growing downwards.
+    pub fn turn_left(self) -> Vector {
+        Vector::new(self.dy, -self.dx)
+    }
+}
+
+impl Add<Vector> for Point {
+    type Output = Point;
+
+    fn add(self, v: Vector) -> Point {
+        Point::new(self.x + v.dx, self.y + v.dy)
+    }
+}
+
+impl AddAssign<Vector> for Point {
+    fn add_assign(&mut self, v: Vector) {
+        *self = *self + v;
+    }
+}
+
+impl Sub<Vector> for Point {
+    type Output = Point;
+
+    fn sub(self, v: Vector) -> Point {
+        self + -v
+    }
+}
+
+impl SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, v: Vector) {
+        *self = *self - v;
+    }
+}
+
+impl Sub for Point {
+    type Output = Vector;
+
+    fn sub(self, other: Point) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.dx + other.dx, self.dy + other.dy)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        self + -other
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.dx, -self.dy)
+    }
+}
+
+impl Mul<i64> for Vector {
+    type Output = Vector;
+
+    fn mul(self, n: i64) -> Vector {
+        Vector::new(self.dx * n, self.dy * n)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// One of the 4 directions on a grid, with north towards the first row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// All the directions, clockwise from north.
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = Direction> {
+        Direction::ALL.into_iter()
+    }
+
+    /// The vector of one step in this direction.
+    pub fn offset(self) -> Vector {
+        match self {
+            Direction::North => Vector::new(0, -1),
+            Direction::East => Vector::new(1, 0),
+            Direction::South => Vector::new(0, 1),
+            Direction::West => Vector::new(-1, 0),
+        }
+    }
+
+    /// The direction with this offset, if it is a single step north, east, south or west.
+    pub fn from_offset(offset: Vector) -> Option<Direction> {
+        Direction::iter().find(|d| d.offset() == offset)
+    }
+
+    pub fn turn_right(self) -> Direction {
+        Direction::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Direction {
+        Direction::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Direction {
+        Direction::ALL[(self as usize + 2) % 4]
+    }
+
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Direction::North | Direction::South)
+    }
+
+    pub fn is_horizontal(self) -> bool {
+        !self.is_vertical()
+    }
+}
+
+#[cfg(test)]
+mod test_geom {
+    use super::{Direction, Point, Vector};
+
+    #[test]
+    fn turn_and_reverse() {
+        for d in Direction::iter() {
+            k9::assert_equal!(d.turn_right().turn_left(), d);
+            k9::assert_equal!(d.turn_right().turn_right(), d.reverse());
+            k9::assert_equal!(d.reverse().reverse(), d);
+            k9::assert_equal!(d.offset().turn_right(), d.turn_right().offset());
+            k9::assert_equal!(d.offset().turn_left(), d.turn_left().offset());
+            k9::assert_equal!(-d.offset(), d.reverse().offset());
+            k9::assert_equal!(Direction::from_offset(d.offset()), Some(d));
+        }
+        k9::assert_equal!(Direction::North.turn_right(), Direction::East);
+        k9::assert_equal!(Direction::North.turn_left(), Direction::West);
+        k9::assert_equal!(Direction::from_offset(Vector::new(1, 1)), None);
+    }
+
+    #[test]
+    fn distances() {
+        let a = Point::new(1, -2);
+        let b = Point::new(-3, 5);
+        k9::assert_equal!(a.manhattan(b), 11);
+        k9::assert_equal!(a.chebyshev(b), 7);
+        k9::assert_equal!(b - a, Vector::new(-4, 7));
+        k9::assert_equal!(a + (b - a), b);
+        k9::assert_equal!(
+            a.step(Direction::North) + Vector::new(0, 1) * 3,
+            Point::new(1, 0)
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let p = Point::new(2, 2);
+        k9::assert_equal!(p.neighbours4().filter(|n| p.manhattan(*n) == 1).count(), 4);
+        k9::assert_equal!(p.neighbours8().filter(|n| p.chebyshev(*n) == 1).count(), 8);
+        k9::assert_equal!(p.neighbours4().next(), Some(Point::new(2, 1)));
+    }
+
+    #[test]
+    fn movement_within_bounds() {
+        let corner = Point::ORIGIN;
+        k9::assert_equal!(corner.step_within(Direction::North, 3, 2), None);
+        k9::assert_equal!(corner.step_within(Direction::West, 3, 2), None);
+        k9::assert_equal!(
+            corner.step_within(Direction::South, 3, 2),
+            Some(Point::new(0, 1))
+        );
+        k9::assert_equal!(Point::new(0, 1).step_within(Direction::South, 3, 2), None);
+        k9::assert_equal!(
+            corner.offset_within(Vector::new(2, 1), 3, 2),
+            Some(Point::new(2, 1))
+        );
+        k9::assert_equal!(corner.offset_within(Vector::new(3, 0), 3, 2), None);
+        k9::assert_equal!(
+            Point::new(i64::MAX, 0).step_within(Direction::East, 3, 2),
+            None
+        );
+    }
+
+    #[test]
+    fn grid_positions() {
+        k9::assert_equal!(Point::from_pos((1, 4)), Point::new(4, 1));
+        k9::assert_equal!(Point::new(4, 1).to_pos(), Some((1, 4)));
+        k9::assert_equal!(Point::new(-1, 1).to_pos(), None);
+    }
+}
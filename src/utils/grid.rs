@@ -0,0 +1,556 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+use super::geom::{Direction, Vector};
+
+/// A position in a grid, as `(row, col)`.
+pub type Pos = (usize, usize);
+
+/// A rectangular grid, stored row by row in a single vector.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Clone> Grid<T> {
+    /// A `width` x `height` grid filled with `value`.
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {
+            cells: vec![value; width * height],
+            width,
+            height,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from its rows, which all need to be the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>> {
+        let width = rows.first().map_or(0, |row| row.len());
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for (i, row) in rows.into_iter().enumerate() {
+            if row.len() != width {
+                bail!("Row {i} has {} cells instead of {width}", row.len());
+            }
+            cells.extend(row);
+        }
+        Ok(Grid {
+            cells,
+            width,
+            height,
+        })
+    }
+
+    /// Parse a grid with one line per row, converting each char with `f`.
+    pub fn parse_with(s: &str, mut f: impl FnMut(char) -> Result<T>) -> Result<Grid<T>> {
+        let rows = s
+            .lines()
+            .enumerate()
+            .map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(col, c)| {
+                        f(c).map_err(|e| e.context(format!("Invalid cell at row {row}, col {col}")))
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Grid::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (row, col): Pos) -> bool {
+        row < self.height && col < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.0 * self.width + pos.1])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        self.contains(pos)
+            .then(|| &mut self.cells[pos.0 * self.width + pos.1])
+    }
+
+    /// The position `(d_row, d_col)` away from `pos`, if it's still in the grid.
+    pub fn offset(&self, (row, col): Pos, (d_row, d_col): (isize, isize)) -> Option<Pos> {
+        let pos = (
+            row.checked_add_signed(d_row)?,
+            col.checked_add_signed(d_col)?,
+        );
+        self.contains(pos).then_some(pos)
+    }
+
+    /// The position one step away from `pos` in `direction`, if it's still in the grid.
+    pub fn step(&self, pos: Pos, direction: Direction) -> Option<Pos> {
+        let Vector { dx, dy } = direction.offset();
+        self.offset(pos, (dy as isize, dx as isize))
+    }
+
+    /// The positions above, right of, below and left of `pos` that are in the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Direction::iter().filter_map(move |d| self.step(pos, d))
+    }
+
+    /// The positions around `pos`, diagonals included, that are in the grid.
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        [
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+            (1, 0),
+            (1, -1),
+            (0, -1),
+        ]
+        .into_iter()
+        .filter_map(move |d| self.offset(pos, d))
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut [T] {
+        &mut self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on 0, and an empty grid has no rows anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.width, "Column {col} out of bounds");
+        self.cells.iter().skip(col).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|col| self.column(col))
+    }
+
+    /// All the cells with their positions, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, c)| ((i / width, i % width), c))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Pos, &mut T)> {
+        let width = self.width;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, c)| ((i / width, i % width), c))
+    }
+
+    /// The positions of the cells matching `f`, row by row.
+    pub fn positions<'a>(
+        &'a self,
+        mut f: impl FnMut(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = Pos> + 'a {
+        self.iter().filter(move |(_, c)| f(c)).map(|(pos, _)| pos)
+    }
+
+    /// The position of the first cell equal to `value`.
+    pub fn find(&self, value: &T) -> Option<Pos>
+    where
+        T: PartialEq,
+    {
+        self.positions(|c| c == value).next()
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.into_iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Swap rows and columns, without allocating a new grid for square ones.
+    pub fn transpose(&mut self) {
+        let (width, height) = (self.width, self.height);
+        if width == height {
+            for row in 0..height {
+                for col in row + 1..width {
+                    self.cells.swap(row * width + col, col * width + row);
+                }
+            }
+        } else {
+            // follow the cycles of the permutation, the cell at `i` goes to `dest(i)`
+            let dest = |i: usize| (i % width) * height + i / width;
+            let mut done = vec![false; self.cells.len()];
+            for start in 0..self.cells.len() {
+                if done[start] {
+                    continue;
+                }
+                let mut i = dest(start);
+                while i != start {
+                    self.cells.swap(start, i);
+                    done[i] = true;
+                    i = dest(i);
+                }
+                done[start] = true;
+            }
+        }
+        self.width = height;
+        self.height = width;
+    }
+
+    /// Mirror the grid left to right.
+    pub fn flip_horizontal(&mut self) {
+        for row in 0..self.height {
+            self.row_mut(row).reverse();
+        }
+    }
+
+    /// Mirror the grid top to bottom.
+    pub fn flip_vertical(&mut self) {
+        for row in 0..self.height / 2 {
+            let other = self.height - 1 - row;
+            for col in 0..self.width {
+                self.cells
+                    .swap(row * self.width + col, other * self.width + col);
+            }
+        }
+    }
+
+    /// Rotate the grid a quarter turn clockwise.
+    pub fn rotate_clockwise(&mut self) {
+        self.transpose();
+        self.flip_horizontal();
+    }
+
+    /// Rotate the grid a quarter turn counterclockwise.
+    pub fn rotate_counterclockwise(&mut self) {
+        self.transpose();
+        self.flip_vertical();
+    }
+
+    /// A read-only view of the grid, which can be transposed, flipped and rotated for free.
+    pub fn view(&self) -> View<'_, T> {
+        View {
+            grid: self,
+            transposed: false,
+            flip_rows: false,
+            flip_cols: false,
+        }
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Grid::parse_with(s, Ok)
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is out of the {}x{} grid", self.height, self.width))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let (height, width) = (self.height, self.width);
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is out of the {height}x{width} grid"))
+    }
+}
+
+/// One line per row, including the last one.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.view(), f)
+    }
+}
+
+/// A grid seen through a transposition and flips, which is enough for any rotation.
+/// Positions are in the transformed grid, and mapped back to the underlying one.
+#[derive(Debug)]
+pub struct View<'a, T> {
+    grid: &'a Grid<T>,
+    transposed: bool,
+    /// Rows are read bottom to top, applied after transposing.
+    flip_rows: bool,
+    /// Columns are read right to left, applied after transposing.
+    flip_cols: bool,
+}
+
+// derived, these would only be implemented for `T: Copy`
+impl<T> Clone for View<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for View<'_, T> {}
+
+impl<'a, T> View<'a, T> {
+    pub fn width(&self) -> usize {
+        if self.transposed {
+            self.grid.height
+        } else {
+            self.grid.width
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.transposed {
+            self.grid.width
+        } else {
+            self.grid.height
+        }
+    }
+
+    pub fn get(&self, (row, col): Pos) -> Option<&'a T> {
+        if row >= self.height() || col >= self.width() {
+            return None;
+        }
+        let row = if self.flip_rows {
+            self.height() - 1 - row
+        } else {
+            row
+        };
+        let col = if self.flip_cols {
+            self.width() - 1 - col
+        } else {
+            col
+        };
+        let pos = if self.transposed {
+            (col, row)
+        } else {
+            (row, col)
+        };
+        self.grid.get(pos)
+    }
+
+    pub fn row(&self, row: usize) -> impl Iterator<Item = &'a T> + '_ {
+        assert!(row < self.height(), "Row {row} out of bounds");
+        (0..self.width()).map(move |col| self.get((row, col)).unwrap())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = &'a T> + '_> + '_ {
+        (0..self.height()).map(|row| self.row(row))
+    }
+
+    pub fn transposed(self) -> View<'a, T> {
+        View {
+            transposed: !self.transposed,
+            flip_rows: self.flip_cols,
+            flip_cols: self.flip_rows,
+            ..self
+        }
+    }
+
+    pub fn flipped_horizontal(self) -> View<'a, T> {
+        View {
+            flip_cols: !self.flip_cols,
+            ..self
+        }
+    }
+
+    pub fn flipped_vertical(self) -> View<'a, T> {
+        View {
+            flip_rows: !self.flip_rows,
+            ..self
+        }
+    }
+
+    pub fn rotated_clockwise(self) -> View<'a, T> {
+        self.transposed().flipped_horizontal()
+    }
+
+    pub fn rotated_counterclockwise(self) -> View<'a, T> {
+        self.transposed().flipped_vertical()
+    }
+
+    /// Copy the view into a grid of its own.
+    pub fn to_grid(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            cells: self.rows().flatten().cloned().collect(),
+            width: self.width(),
+            height: self.height(),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for View<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_grid {
+    use super::Grid;
+    use crate::utils::geom::Direction;
+
+    const EXAMPLE: &str = "abc\ndef\n";
+
+    fn example() -> Grid<char> {
+        EXAMPLE.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let grid = example();
+        k9::assert_equal!((grid.width(), grid.height()), (3, 2));
+        k9::assert_equal!(grid[(1, 0)], 'd');
+        k9::assert_equal!(grid.get((2, 0)), None);
+        k9::assert_equal!(grid.to_string(), EXAMPLE);
+        assert!("ab\nc\n".parse::<Grid<char>>().is_err());
+
+        let digits = Grid::parse_with("12\n34", |c| {
+            c.to_digit(10)
+                .ok_or_else(|| anyhow::anyhow!("{c} is not a digit"))
+        })
+        .unwrap();
+        k9::assert_equal!(digits.iter().map(|(_, d)| d).sum::<u32>(), 10);
+        let err = Grid::parse_with("12\n3x", |c| {
+            c.to_digit(10)
+                .ok_or_else(|| anyhow::anyhow!("{c} is not a digit"))
+        })
+        .unwrap_err();
+        k9::assert_equal!(
+            format!("{err:#}"),
+            "Invalid cell at row 1, col 1: x is not a digit"
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = example();
+        k9::assert_equal!(
+            grid.neighbours4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        k9::assert_equal!(grid.neighbours8((0, 1)).count(), 5);
+        k9::assert_equal!(grid.offset((1, 2), (0, 1)), None);
+        k9::assert_equal!(grid.offset((1, 2), (-1, -2)), Some((0, 0)));
+        k9::assert_equal!(grid.step((1, 2), Direction::North), Some((0, 2)));
+        k9::assert_equal!(grid.step((1, 2), Direction::East), None);
+    }
+
+    #[test]
+    fn rows_columns_and_search() {
+        let grid = example();
+        k9::assert_equal!(grid.row(1), &['d', 'e', 'f']);
+        k9::assert_equal!(grid.column(1).collect::<String>(), "be");
+        k9::assert_equal!(
+            grid.columns()
+                .map(|c| c.collect::<String>())
+                .collect::<Vec<_>>(),
+            vec!["ad", "be", "cf"]
+        );
+        k9::assert_equal!(grid.find(&'e'), Some((1, 1)));
+        k9::assert_equal!(grid.find(&'z'), None);
+        k9::assert_equal!(
+            grid.positions(|c| "aeiou".contains(*c)).collect::<Vec<_>>(),
+            vec![(0, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn in_place_transforms() {
+        let mut grid = example();
+        grid.transpose();
+        k9::assert_equal!(grid.to_string(), "ad\nbe\ncf\n");
+        grid.transpose();
+        k9::assert_equal!(grid, example());
+
+        grid.rotate_clockwise();
+        k9::assert_equal!(grid.to_string(), "da\neb\nfc\n");
+        grid.rotate_counterclockwise();
+        k9::assert_equal!(grid, example());
+
+        grid.flip_horizontal();
+        k9::assert_equal!(grid.to_string(), "cba\nfed\n");
+        grid.flip_vertical();
+        k9::assert_equal!(grid.to_string(), "fed\ncba\n");
+
+        let mut square = "ab\ncd".parse::<Grid<char>>().unwrap();
+        square.transpose();
+        k9::assert_equal!(square.to_string(), "ac\nbd\n");
+    }
+
+    #[test]
+    fn views_match_in_place_transforms() {
+        let grid = "abcd\nefgh\nijkl\n".parse::<Grid<char>>().unwrap();
+        let view = grid.view();
+        let transforms = [
+            (Grid::transpose as fn(&mut Grid<char>), view.transposed()),
+            (Grid::flip_horizontal, view.flipped_horizontal()),
+            (Grid::flip_vertical, view.flipped_vertical()),
+            (Grid::rotate_clockwise, view.rotated_clockwise()),
+            (
+                Grid::rotate_counterclockwise,
+                view.rotated_counterclockwise(),
+            ),
+        ];
+        for (transform, view) in transforms {
+            let mut expected = grid.clone();
+            transform(&mut expected);
+            k9::assert_equal!(view.to_grid(), expected);
+            k9::assert_equal!(view.to_string(), expected.to_string());
+        }
+        // four quarter turns are back to the start
+        let view = view.rotated_clockwise().rotated_clockwise();
+        let view = view.rotated_clockwise().rotated_clockwise();
+        k9::assert_equal!(view.to_grid(), grid);
+        k9::assert_equal!(
+            grid.view().rotated_clockwise().transposed().to_string(),
+            "ijkl\nefgh\nabcd\n"
+        );
+    }
+
+    #[test]
+    fn map_and_mutate() {
+        let mut grid = example().map(|c| c as u32 - 'a' as u32);
+        grid[(0, 0)] = 10;
+        for (_, cell) in grid.iter_mut() {
+            *cell += 1;
+        }
+        k9::assert_equal!(grid.to_string(), "1123\n456\n");
+        k9::assert_equal!(Grid::new(2, 1, 0).to_string(), "00\n");
+        k9::assert_equal!(
+            Grid::from_rows(Vec::<Vec<u8>>::new())
+                .unwrap()
+                .rows()
+                .count(),
+            0
+        );
+    }
+}
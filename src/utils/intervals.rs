@@ -0,0 +1,320 @@
+use std::fmt;
+use std::ops::Range;
+
+/// A set of integers, stored as the sorted list of the half-open ranges it's made of.
+/// The ranges are never empty, and never overlap or touch each other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct IntervalSet {
+    ranges: Vec<Range<i64>>,
+}
+
+/// Move the integers of `source` by `offset`, e.g. a line of a day 5 map.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pub source: Range<i64>,
+    pub offset: i64,
+}
+
+impl Rule {
+    /// The rule sending `source..source + len` to `destination..destination + len`.
+    pub fn new(destination: i64, source: i64, len: i64) -> Rule {
+        Rule {
+            source: source..source + len,
+            offset: destination - source,
+        }
+    }
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet::default()
+    }
+
+    /// Build a set from ranges that are sorted and separated by at least one integer,
+    /// skipping the empty ones.
+    fn from_sorted(ranges: impl IntoIterator<Item = Range<i64>>) -> IntervalSet {
+        let mut set = IntervalSet::new();
+        for range in ranges {
+            set.push(range);
+        }
+        set
+    }
+
+    /// Add a range starting after the start of all the others, merging it with the last one
+    /// if they overlap or touch.
+    fn push(&mut self, range: Range<i64>) {
+        if range.is_empty() {
+            return;
+        }
+        match self.ranges.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => self.ranges.push(range),
+        }
+    }
+
+    /// The ranges making up the set, sorted.
+    pub fn ranges(&self) -> &[Range<i64>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers in the set.
+    pub fn len(&self) -> i64 {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        // the first range ending after `value` is the only one that can contain it
+        let i = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(i).is_some_and(|r| r.start <= value)
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn max(&self) -> Option<i64> {
+        self.ranges.last().map(|r| r.end - 1)
+    }
+
+    pub fn insert(&mut self, range: Range<i64>) {
+        *self = self.union(&IntervalSet::from(range));
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = [&self.ranges[..], &other.ranges[..]].concat();
+        ranges.sort_unstable_by_key(|r| r.start);
+        IntervalSet::from_sorted(ranges)
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut res = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            res.push(a.start.max(b.start)..a.end.min(b.end));
+            // the range ending first can't overlap anything else of the other set
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        res
+    }
+
+    /// The integers of this set that are not in `other`.
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut res = IntervalSet::new();
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            // skip the ranges of `other` that end before this one, they can't overlap the next
+            // ones either
+            while other.ranges.get(j).is_some_and(|r| r.end <= start) {
+                j += 1;
+            }
+            let mut k = j;
+            while let Some(cut) = other.ranges.get(k).filter(|r| r.start < range.end) {
+                res.push(start..cut.start);
+                start = start.max(cut.end);
+                k += 1;
+            }
+            res.push(start..range.end);
+        }
+        res
+    }
+
+    /// The set moved by `offset`.
+    pub fn shift(&self, offset: i64) -> IntervalSet {
+        IntervalSet {
+            ranges: self
+                .ranges
+                .iter()
+                .map(|r| r.start + offset..r.end + offset)
+                .collect(),
+        }
+    }
+
+    /// The set with only its integers in `range` moved by `offset`.
+    pub fn translate(&self, range: Range<i64>, offset: i64) -> IntervalSet {
+        self.map(&[Rule {
+            source: range,
+            offset,
+        }])
+    }
+
+    /// Split the set along the sources of `rules`: the part each rule applies to, before
+    /// moving it, and the part no rule applies to. When rules overlap, the first one wins.
+    pub fn split(&self, rules: &[Rule]) -> (Vec<IntervalSet>, IntervalSet) {
+        let mut rest = self.clone();
+        let parts = rules
+            .iter()
+            .map(|rule| {
+                let source = IntervalSet::from(rule.source.clone());
+                let part = rest.intersection(&source);
+                rest = rest.difference(&source);
+                part
+            })
+            .collect();
+        (parts, rest)
+    }
+
+    /// Apply `rules` to every integer of the set, keeping the ones no rule applies to
+    /// where they are. When rules overlap, the first one wins.
+    pub fn map(&self, rules: &[Rule]) -> IntervalSet {
+        let (parts, rest) = self.split(rules);
+        parts.iter().zip(rules).fold(rest, |res, (part, rule)| {
+            res.union(&part.shift(rule.offset))
+        })
+    }
+}
+
+impl From<Range<i64>> for IntervalSet {
+    fn from(range: Range<i64>) -> IntervalSet {
+        IntervalSet::from_sorted([range])
+    }
+}
+
+impl FromIterator<Range<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Range<i64>>>(iter: I) -> IntervalSet {
+        let mut ranges = iter.into_iter().collect::<Vec<_>>();
+        ranges.sort_unstable_by_key(|r| r.start);
+        IntervalSet::from_sorted(ranges)
+    }
+}
+
+/// The ranges, e.g. `{1..3, 5..8}`.
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|r| format!("{r:?}"))
+            .collect::<Vec<_>>();
+        write!(f, "{{{}}}", ranges.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test_intervals {
+    use super::{IntervalSet, Rule};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeSet;
+
+    /// Small domain, so that random sets overlap and touch a lot.
+    const DOMAIN: i64 = 40;
+
+    fn random_ranges(rng: &mut StdRng) -> Vec<std::ops::Range<i64>> {
+        (0..rng.gen_range(0..5))
+            .map(|_| {
+                let start = rng.gen_range(0..DOMAIN);
+                start..rng.gen_range(start..=DOMAIN)
+            })
+            .collect()
+    }
+
+    fn naive(ranges: &[std::ops::Range<i64>]) -> BTreeSet<i64> {
+        ranges.iter().flat_map(|r| r.clone()).collect()
+    }
+
+    fn integers(set: &IntervalSet) -> BTreeSet<i64> {
+        naive(set.ranges())
+    }
+
+    fn assert_normalized(set: &IntervalSet) {
+        assert!(set.ranges().iter().all(|r| !r.is_empty()), "{set}");
+        assert!(
+            set.ranges().windows(2).all(|w| w[0].end < w[1].start),
+            "{set}"
+        );
+    }
+
+    #[test]
+    fn basics() {
+        let mut set = [5..8, 1..3, 2..4, 8..9, 12..12]
+            .into_iter()
+            .collect::<IntervalSet>();
+        k9::assert_equal!(set.to_string(), "{1..4, 5..9}");
+        k9::assert_equal!(set.len(), 7);
+        k9::assert_equal!((set.min(), set.max()), (Some(1), Some(8)));
+        assert!(set.contains(3) && !set.contains(4) && set.contains(5) && !set.contains(9));
+        set.insert(4..5);
+        k9::assert_equal!(set.to_string(), "{1..9}");
+        k9::assert_equal!(IntervalSet::new().min(), None);
+    }
+
+    #[test]
+    fn day5_rules() {
+        // the seed-to-soil map of the example
+        let rules = [Rule::new(50, 98, 2), Rule::new(52, 50, 48)];
+        let seeds = [79..93, 55..68].into_iter().collect::<IntervalSet>();
+        k9::assert_equal!(seeds.map(&rules).ranges(), &[57..70, 81..95]);
+
+        let (parts, rest) = IntervalSet::from(0..100).split(&rules);
+        k9::assert_equal!(parts[0].to_string(), "{98..100}");
+        k9::assert_equal!(parts[1].to_string(), "{50..98}");
+        k9::assert_equal!(rest.to_string(), "{0..50}");
+        k9::assert_equal!(
+            IntervalSet::from(0..10).translate(5..7, 100).to_string(),
+            "{0..5, 7..10, 105..107}"
+        );
+    }
+
+    #[test]
+    fn matches_naive_model() {
+        for seed in 0..500 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (a, b) = (random_ranges(&mut rng), random_ranges(&mut rng));
+            let (set_a, set_b) = (
+                a.iter().cloned().collect::<IntervalSet>(),
+                b.iter().cloned().collect::<IntervalSet>(),
+            );
+            let (naive_a, naive_b) = (naive(&a), naive(&b));
+
+            for set in [&set_a, &set_b] {
+                assert_normalized(set);
+            }
+            k9::assert_equal!(integers(&set_a), naive_a);
+            k9::assert_equal!(set_a.len(), naive_a.len() as i64);
+            k9::assert_equal!(set_a.min(), naive_a.first().copied());
+            k9::assert_equal!(set_a.max(), naive_a.last().copied());
+            for x in -1..=DOMAIN {
+                k9::assert_equal!(set_a.contains(x), naive_a.contains(&x));
+            }
+
+            let union = set_a.union(&set_b);
+            let intersection = set_a.intersection(&set_b);
+            let difference = set_a.difference(&set_b);
+            for set in [&union, &intersection, &difference] {
+                assert_normalized(set);
+            }
+            k9::assert_equal!(integers(&union), &naive_a | &naive_b);
+            k9::assert_equal!(integers(&intersection), &naive_a & &naive_b);
+            k9::assert_equal!(integers(&difference), &naive_a - &naive_b);
+
+            let rules = random_ranges(&mut rng)
+                .into_iter()
+                .map(|source| Rule {
+                    source,
+                    offset: rng.gen_range(-DOMAIN..DOMAIN),
+                })
+                .collect::<Vec<_>>();
+            let mapped = set_a.map(&rules);
+            assert_normalized(&mapped);
+            let naive_mapped = naive_a
+                .iter()
+                .map(|x| {
+                    rules
+                        .iter()
+                        .find(|rule| rule.source.contains(x))
+                        .map_or(*x, |rule| x + rule.offset)
+                })
+                .collect::<BTreeSet<_>>();
+            k9::assert_equal!(integers(&mapped), naive_mapped);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+
+/// The gcd of `a` and `b`, never negative, along with Bézout coefficients `x` and `y` such
+/// that `a * x + b * y == gcd`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    extended_gcd(a, b).0
+}
+
+/// The lcm of `a` and `b`, never negative, or `None` if it doesn't fit in an `i128`.
+pub fn lcm(a: i128, b: i128) -> Option<i128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)?.checked_abs()
+}
+
+/// `a + b` modulo `m`, for `a` and `b` already in `0..m`, without overflowing.
+fn add_mod(a: i128, b: i128, m: i128) -> i128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// `a * b` modulo `m`, in `0..m`, without overflowing even when the product doesn't fit.
+pub fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    let (mut a, mut b) = (a.rem_euclid(m), b.rem_euclid(m));
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+    // double and add, which only ever needs numbers below 2 * m
+    let mut res = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            res = add_mod(res, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    res
+}
+
+/// `base` to the power `exp` modulo `m`, in `0..m`.
+pub fn pow_mod(base: i128, mut exp: u128, m: i128) -> i128 {
+    let mut base = base.rem_euclid(m);
+    let mut res = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = mul_mod(res, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    res
+}
+
+/// The `x` in `0..m` such that `a * x ≡ 1 (mod m)`, if `a` and `m` are coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    assert!(m > 0, "the modulus must be positive, got {m}");
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// The integers `x` such that `x ≡ residue (mod modulus)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Congruence {
+    /// Always in `0..modulus`.
+    pub residue: i128,
+    pub modulus: i128,
+}
+
+impl Congruence {
+    pub fn new(residue: i128, modulus: i128) -> Congruence {
+        assert!(modulus > 0, "the modulus must be positive, got {modulus}");
+        Congruence {
+            residue: residue.rem_euclid(modulus),
+            modulus,
+        }
+    }
+
+    pub fn contains(&self, x: i128) -> bool {
+        x.rem_euclid(self.modulus) == self.residue
+    }
+
+    /// The smallest integer of the congruence that is at least `min`.
+    pub fn first_from(&self, min: i128) -> i128 {
+        min + (self.residue - min).rem_euclid(self.modulus)
+    }
+
+    /// The integers in both congruences, if there are any. The moduli don't need to be
+    /// coprime, which is the generalized Chinese remainder theorem.
+    ///
+    /// Panics if the combined modulus, the lcm of both, doesn't fit in an `i128`.
+    pub fn combine(&self, other: &Congruence) -> Option<Congruence> {
+        let (g, p, _) = extended_gcd(self.modulus, other.modulus);
+        // both residues are in range, so this can't overflow
+        let diff = other.residue - self.residue;
+        if diff % g != 0 {
+            return None;
+        }
+        // the solutions are `self.residue + self.modulus * k` with `k` a solution of
+        // `self.modulus / g * k ≡ diff / g (mod step)`, whose inverse is `p`
+        let step = other.modulus / g;
+        let k = mul_mod(diff / g, p, step);
+        let modulus = self
+            .modulus
+            .checked_mul(step)
+            .unwrap_or_else(|| panic!("combining {self} and {other} overflows"));
+        // at most `modulus - 1`, so this fits too
+        Some(Congruence::new(self.residue + self.modulus * k, modulus))
+    }
+}
+
+/// E.g. `2 (mod 3)`.
+impl fmt::Display for Congruence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (mod {})", self.residue, self.modulus)
+    }
+}
+
+/// The integers satisfying all the congruences, if there are any. With no congruences at all,
+/// that's every integer.
+pub fn crt(congruences: impl IntoIterator<Item = Congruence>) -> Option<Congruence> {
+    congruences
+        .into_iter()
+        .try_fold(Congruence::new(0, 1), |res, c| res.combine(&c))
+}
+
+/// The first time all the periodic events happen together, if they ever do. Each event is
+/// an `(offset, period)` pair, happening at `offset`, `offset + period` and so on, e.g. a
+/// ghost of day 8 reaching an end node.
+pub fn first_common_time(events: &[(i128, i128)]) -> Option<i128> {
+    let start = events.iter().map(|(offset, _)| *offset).max()?;
+    let times = crt(events
+        .iter()
+        .map(|&(offset, period)| Congruence::new(offset, period)))?;
+    Some(times.first_from(start))
+}
+
+#[cfg(test)]
+mod test_numtheory {
+    use super::{
+        crt, extended_gcd, first_common_time, lcm, mod_inverse, mul_mod, pow_mod, Congruence,
+    };
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn gcd_and_inverse() {
+        k9::assert_equal!(extended_gcd(240, 46), (2, -9, 47));
+        k9::assert_equal!(extended_gcd(-4, 6).0, 2);
+        k9::assert_equal!(extended_gcd(0, 0), (0, 1, 0));
+        k9::assert_equal!(lcm(4, 6), Some(12));
+        k9::assert_equal!(lcm(i128::MAX, i128::MAX - 1), None);
+        k9::assert_equal!(mod_inverse(3, 11), Some(4));
+        k9::assert_equal!(mod_inverse(-3, 11), Some(7));
+        k9::assert_equal!(mod_inverse(4, 6), None);
+
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (a, b) = (rng.gen_range(-1000..1000), rng.gen_range(-1000..1000));
+            let (g, x, y) = extended_gcd(a, b);
+            k9::assert_equal!(a * x + b * y, g);
+            assert!(g >= 0);
+            if g > 0 {
+                k9::assert_equal!((a % g, b % g), (0, 0));
+            }
+        }
+    }
+
+    #[test]
+    fn no_overflow_with_big_moduli() {
+        let m = (1 << 125) - 1;
+        k9::assert_equal!(mul_mod(m - 1, m - 1, m), 1);
+        k9::assert_equal!(mul_mod(-1, m - 2, m), 2);
+        // Fermat's little theorem, with a Mersenne prime
+        let p = i128::MAX;
+        k9::assert_equal!(pow_mod(3, p as u128 - 1, p), 1);
+        k9::assert_equal!(mul_mod(3, mod_inverse(3, p).unwrap(), p), 1);
+
+        // both moduli are around 2^62, so their lcm doesn't fit in an i64
+        let (a, b) = (4_611_686_018_427_387_847, 4_611_686_018_427_387_817);
+        let both = Congruence::new(5, a)
+            .combine(&Congruence::new(7, b))
+            .unwrap();
+        k9::assert_equal!(both.modulus, a * b);
+        assert!(both.residue % a == 5 && both.residue % b == 7);
+    }
+
+    #[test]
+    fn non_coprime_moduli() {
+        k9::assert_equal!(
+            Congruence::new(2, 6).combine(&Congruence::new(8, 10)),
+            Some(Congruence::new(8, 30))
+        );
+        // x would have to be both even and odd
+        k9::assert_equal!(Congruence::new(2, 6).combine(&Congruence::new(3, 4)), None);
+        k9::assert_equal!(crt([]), Some(Congruence::new(0, 1)));
+        k9::assert_equal!(Congruence::new(-1, 7).to_string(), "6 (mod 7)");
+    }
+
+    #[test]
+    fn crt_matches_brute_force() {
+        for seed in 0..300 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let congruences = (0..rng.gen_range(1..4))
+                .map(|_| {
+                    let modulus = rng.gen_range(1..13);
+                    Congruence::new(rng.gen_range(0..modulus), modulus)
+                })
+                .collect::<Vec<_>>();
+            let period = congruences
+                .iter()
+                .fold(1, |acc, c| lcm(acc, c.modulus).unwrap());
+            let expected = (0..period).find(|x| congruences.iter().all(|c| c.contains(*x)));
+            k9::assert_equal!(
+                crt(congruences.iter().copied()),
+                expected.map(|x| Congruence::new(x, period))
+            );
+        }
+    }
+
+    #[test]
+    fn periodic_events() {
+        k9::assert_equal!(first_common_time(&[(2, 2), (3, 3)]), Some(6));
+        // the first time has to be after every offset, not just in the right congruence
+        k9::assert_equal!(first_common_time(&[(5, 3), (1, 2)]), Some(5));
+        k9::assert_equal!(first_common_time(&[(9, 3), (1, 2)]), Some(9));
+        k9::assert_equal!(first_common_time(&[(1, 4), (3, 4)]), None);
+        k9::assert_equal!(first_common_time(&[]), None);
+    }
+}
@@ -0,0 +1,406 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use super::grid::Grid;
+
+/// What went wrong while parsing, and where. Lines and columns start at 1, like in editors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    /// Number of chars the error is about, starting at the column. Never past the end of the line.
+    pub len: usize,
+    pub message: String,
+    /// What the input should look like instead, when there is something useful to say.
+    pub hint: Option<String>,
+}
+
+impl ParseError {
+    pub fn with_hint(mut self, hint: impl fmt::Display) -> ParseError {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+/// A piece of the input that knows where it starts, so that errors about it can point
+/// to the right line and column. Splitting it gives pieces that know where they are too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Text<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Text<'a> {
+    /// The whole input, starting on line 1, column 1.
+    pub fn new(text: &'a str) -> Text<'a> {
+        Text {
+            text,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The line and column this piece starts at.
+    pub fn position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+
+    /// An error about this piece, or the part of it on its first line.
+    pub fn error(&self, message: impl fmt::Display) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            len: self.text.lines().next().unwrap_or_default().chars().count(),
+            message: message.to_string(),
+            hint: None,
+        }
+    }
+
+    /// The piece starting `offset` bytes into this one, up to its end.
+    fn at(&self, offset: usize) -> Text<'a> {
+        Cursor::new(*self).sub(&self.text[offset..])
+    }
+
+    /// The empty piece right after this one, e.g. to complain about something missing.
+    pub fn end(&self) -> Text<'a> {
+        self.at(self.text.len())
+    }
+
+    /// Locate `part`, which must be a slice of this piece, as returned by the `str` methods.
+    /// Use a [`Cursor`] to locate many parts in a row.
+    fn sub(&self, part: &'a str) -> Text<'a> {
+        Cursor::new(*self).sub(part)
+    }
+
+    /// The bytes of the piece in `range`, which panics when out of bounds like `&str[range]`.
+    pub fn slice(&self, range: Range<usize>) -> Text<'a> {
+        self.sub(&self.text[range])
+    }
+
+    pub fn trim(&self) -> Text<'a> {
+        self.sub(self.text.trim())
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = Text<'a>> + '_ {
+        let mut cursor = Cursor::new(*self);
+        self.text.lines().map(move |line| cursor.sub(line))
+    }
+
+    /// The groups of lines separated by blank lines, e.g. the maps of day 5.
+    pub fn sections(&self) -> impl Iterator<Item = Text<'a>> + '_ {
+        let mut cursor = Cursor::new(*self);
+        self.text
+            .split("\n\n")
+            .filter(|section| !section.trim().is_empty())
+            .map(move |section| cursor.sub(section.trim_matches('\n')))
+    }
+
+    pub fn split(&self, separator: &'a str) -> impl Iterator<Item = Text<'a>> + '_ {
+        let mut cursor = Cursor::new(*self);
+        self.text.split(separator).map(move |part| cursor.sub(part))
+    }
+
+    pub fn split_whitespace(&self) -> impl Iterator<Item = Text<'a>> + '_ {
+        let mut cursor = Cursor::new(*self);
+        self.text
+            .split_whitespace()
+            .map(move |word| cursor.sub(word))
+    }
+
+    /// The parts before and after the first `separator`, which has to be there.
+    pub fn split_once(&self, separator: &str) -> Result<(Text<'a>, Text<'a>)> {
+        let (before, after) = self
+            .text
+            .split_once(separator)
+            .ok_or_else(|| self.error(format!("expected `{separator}` in `{}`", self.text)))?;
+        Ok((self.sub(before), self.sub(after)))
+    }
+
+    /// The rest of the piece after `prefix`, which has to be there.
+    pub fn strip_prefix(&self, prefix: &str) -> Result<Text<'a>> {
+        let rest = self
+            .text
+            .strip_prefix(prefix)
+            .ok_or_else(|| self.error(format!("expected `{prefix}`")))?;
+        Ok(self.sub(rest))
+    }
+
+    /// The piece without `suffix`, which has to be there.
+    pub fn strip_suffix(&self, suffix: &str) -> Result<Text<'a>> {
+        let rest = self.text.strip_suffix(suffix).ok_or_else(|| {
+            self.end()
+                .error(format!("expected `{suffix}` at the end of `{}`", self.text))
+        })?;
+        Ok(self.sub(rest))
+    }
+
+    /// The values after `key:` on a line like `Time:  7  15  30`.
+    pub fn field(&self, key: &str) -> Result<Text<'a>> {
+        let (name, values) = self.split_once(":")?;
+        if name.text.trim() != key {
+            return Err(name.error(format!("expected `{key}:`, found `{}:`", name.text)));
+        }
+        Ok(values.trim())
+    }
+
+    /// Parse the whole piece, ignoring the whitespace around it.
+    pub fn parse<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let trimmed = self.trim();
+        trimmed
+            .text
+            .parse()
+            .map_err(|e| trimmed.error(format!("invalid value `{}`: {e}", trimmed.text)))
+    }
+
+    /// All the integers in the piece, whatever is around them, e.g. `[1, -2]` in `a 1, b -2`.
+    /// A `-` right before digits is a minus sign, unless it's part of a word like `seed-to-soil`.
+    pub fn integers<T>(&self) -> Result<Vec<T>>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let bytes = self.text.as_bytes();
+        let mut cursor = Cursor::new(*self);
+        let mut res = vec![];
+        let mut i = 0;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_digit() {
+                i += 1;
+                continue;
+            }
+            let mut start = i;
+            if start > 0
+                && bytes[start - 1] == b'-'
+                && (start == 1 || !bytes[start - 2].is_ascii_alphanumeric())
+            {
+                start -= 1;
+            }
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            res.push(cursor.sub(&self.text[start..i]).parse()?);
+        }
+        Ok(res)
+    }
+
+    /// A grid with one line per row, all of the same length, converting each char with `f`.
+    /// A `None` from `f` is an invalid char.
+    pub fn grid<T>(&self, mut f: impl FnMut(char) -> Option<T>) -> Result<Grid<T>> {
+        let mut rows = vec![];
+        for line in self.lines() {
+            let row = line
+                .text
+                .char_indices()
+                .map(|(i, c)| {
+                    f(c).ok_or_else(|| {
+                        line.slice(i..i + c.len_utf8())
+                            .error(format!("unexpected `{c}` in grid"))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if let Some(first) = rows.first().map(Vec::len) {
+                if row.len() != first {
+                    return Err(line.error(format!(
+                        "expected a row of {first} cells, found {}",
+                        row.len()
+                    )));
+                }
+            }
+            rows.push(row);
+        }
+        Grid::from_rows(rows).map_err(|e| self.error(e))
+    }
+}
+
+/// Walks through a piece, keeping track of the line and column it's at, so that locating
+/// its parts one after the other only goes over each char once, rather than counting the
+/// lines again from the start of the piece for each part.
+struct Cursor<'a> {
+    piece: Text<'a>,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(piece: Text<'a>) -> Cursor<'a> {
+        Cursor {
+            piece,
+            offset: 0,
+            line: piece.line,
+            column: piece.column,
+        }
+    }
+
+    /// Locate `part`, a slice of the piece that can't start before the previous one.
+    fn sub(&mut self, part: &'a str) -> Text<'a> {
+        let offset = part.as_ptr() as usize - self.piece.text.as_ptr() as usize;
+        debug_assert!(self.offset <= offset && offset + part.len() <= self.piece.text.len());
+        let skipped = &self.piece.text[self.offset..offset];
+        match skipped.rfind('\n') {
+            Some(newline) => {
+                self.line += skipped.matches('\n').count();
+                self.column = skipped[newline + 1..].chars().count() + 1;
+            }
+            None => self.column += skipped.chars().count(),
+        }
+        self.offset = offset;
+        Text {
+            text: part,
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_parse {
+    use super::{ParseError, Text};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn positions() {
+        let input = Text::new("ab\ncd ef\n\ngh: 1 2\n");
+        let lines = input.lines().collect::<Vec<_>>();
+        k9::assert_equal!(lines[1].position(), (2, 1));
+        let words = lines[1].split_whitespace().collect::<Vec<_>>();
+        k9::assert_equal!(words[1].as_str(), "ef");
+        k9::assert_equal!(words[1].position(), (2, 4));
+
+        let sections = input.sections().collect::<Vec<_>>();
+        k9::assert_equal!(sections.len(), 2);
+        k9::assert_equal!(sections[1].as_str(), "gh: 1 2");
+        k9::assert_equal!(sections[1].position(), (4, 1));
+        k9::assert_equal!(sections[1].field("gh").unwrap().position(), (4, 5));
+    }
+
+    #[test]
+    fn positions_match_counting_from_the_start() {
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let input = (0..rng.gen_range(0..200))
+                .map(|_| ['a', 'é', ' ', '\n', '1', ','][rng.gen_range(0..6)])
+                .collect::<String>();
+            let input = Text::new(&input);
+            // where a part starts, counting the lines and chars before it
+            let expected = |part: Text| {
+                let offset = part.as_str().as_ptr() as usize - input.as_str().as_ptr() as usize;
+                let before = &input.as_str()[..offset];
+                let line = before.matches('\n').count() + 1;
+                let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+                (line, column)
+            };
+            let lines = input.lines().collect::<Vec<_>>();
+            for part in lines
+                .iter()
+                .copied()
+                .chain(input.sections())
+                .chain(input.split(","))
+                .chain(input.split_whitespace())
+                .chain(lines.iter().flat_map(|line| line.split_whitespace()))
+            {
+                k9::assert_equal!(part.position(), expected(part));
+            }
+            k9::assert_equal!(input.end().position(), expected(input.end()));
+        }
+    }
+
+    #[test]
+    fn integers() {
+        let line = Text::new("Game 12: -3 red,4-5 seed-to-soil 007");
+        k9::assert_equal!(line.integers::<i64>().unwrap(), vec![12, -3, 4, 5, 7]);
+        assert!(Text::new("").integers::<i64>().unwrap().is_empty());
+        k9::assert_equal!(
+            Text::new("1\n 2 300").integers::<u8>().unwrap_err(),
+            ParseError {
+                line: 2,
+                column: 4,
+                len: 3,
+                message: "invalid value `300`: number too large to fit in target type".to_string(),
+                hint: None,
+            }
+        );
+    }
+
+    #[test]
+    fn fields() {
+        let line = Text::new("Time:      7  15   30");
+        k9::assert_equal!(
+            line.field("Time").unwrap().integers::<i32>().unwrap(),
+            vec![7, 15, 30]
+        );
+        k9::assert_equal!(
+            line.field("Distance").unwrap_err().to_string(),
+            "line 1, column 1: expected `Distance:`, found `Time:`"
+        );
+        k9::assert_equal!(
+            Text::new("x\nTime 7")
+                .lines()
+                .nth(1)
+                .unwrap()
+                .field("Time")
+                .unwrap_err()
+                .to_string(),
+            "line 2, column 1: expected `:` in `Time 7`"
+        );
+        k9::assert_equal!(
+            Text::new("(a, b")
+                .strip_suffix(")")
+                .unwrap_err()
+                .to_string(),
+            "line 1, column 6: expected `)` at the end of `(a, b`"
+        );
+    }
+
+    #[test]
+    fn grids() {
+        let grid = Text::new("#.\n.#\n")
+            .grid(|c| match c {
+                '#' => Some(true),
+                '.' => Some(false),
+                _ => None,
+            })
+            .unwrap();
+        k9::assert_equal!(grid[(1, 1)], true);
+        k9::assert_equal!(
+            Text::new("#.\n.x\n")
+                .grid(|c| (c != 'x').then_some(c))
+                .unwrap_err()
+                .to_string(),
+            "line 2, column 2: unexpected `x` in grid"
+        );
+        k9::assert_equal!(
+            Text::new("#.\n.\n").grid(Some).unwrap_err().to_string(),
+            "line 2, column 1: expected a row of 2 cells, found 1"
+        );
+    }
+}
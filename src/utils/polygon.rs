@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+
+use num::integer::gcd;
+
+use super::geom::{Direction, Point, Vector};
+
+/// A simple polygon, i.e. one whose edges don't cross, with its corners on integer points,
+/// e.g. the loop of pipes of day 10 or a dig plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    /// The polygon going through `vertices` in order, and back to the first one. Vertices
+    /// in the middle of an edge are fine, so a loop can be given tile by tile.
+    pub fn new(vertices: Vec<Point>) -> Polygon {
+        Polygon { vertices }
+    }
+
+    /// The polygon drawn by walking from `start`, e.g. `R 6` then `D 5` for a dig plan. The
+    /// walk should end back on `start`.
+    pub fn from_steps(start: Point, steps: impl IntoIterator<Item = (Direction, i64)>) -> Polygon {
+        let mut vertices = vec![start];
+        let mut current = start;
+        for (direction, len) in steps {
+            current += direction.offset() * len;
+            vertices.push(current);
+        }
+        if vertices.len() > 1 && vertices.last() == Some(&start) {
+            vertices.pop();
+        }
+        Polygon { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// The edges, each one from a vertex to the next, the last one closing the polygon.
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+    }
+
+    /// Twice the area given by the shoelace formula, which is always an integer. With `y`
+    /// growing downwards, it's positive when the vertices go clockwise on screen.
+    pub fn twice_signed_area(&self) -> i64 {
+        self.edges().map(|(a, b)| a.x * b.y - b.x * a.y).sum()
+    }
+
+    /// Twice the area, as it's only ever a multiple of a half.
+    pub fn twice_area(&self) -> i64 {
+        self.twice_signed_area().abs()
+    }
+
+    /// Number of integer points on the edges. Without any area, edges can go back over each
+    /// other, e.g. a segment is walked there and back, so the distinct points are counted.
+    pub fn boundary_points(&self) -> i64 {
+        if self.twice_area() == 0 {
+            return self.distinct_boundary_points();
+        }
+        self.edges()
+            .map(|(a, b)| gcd((b.x - a.x).abs(), (b.y - a.y).abs()))
+            .sum()
+    }
+
+    /// Walk each edge point by point, which is only reasonable for the short degenerate ones.
+    fn distinct_boundary_points(&self) -> i64 {
+        let mut points = HashSet::new();
+        for (a, b) in self.edges() {
+            points.insert(a);
+            let offset = b - a;
+            let steps = gcd(offset.dx.abs(), offset.dy.abs());
+            if steps > 0 {
+                let step = Vector::new(offset.dx / steps, offset.dy / steps);
+                points.extend((1..=steps).map(|i| a + step * i));
+            }
+        }
+        points.len() as i64
+    }
+
+    /// Number of integer points strictly inside, from Pick's theorem:
+    /// `area = interior + boundary / 2 - 1`. A polygon without any area, e.g. with fewer than
+    /// 3 vertices or all of them on a line, has nothing inside, which the theorem doesn't cover.
+    pub fn interior_points(&self) -> i64 {
+        let twice_area = self.twice_area();
+        if twice_area == 0 {
+            return 0;
+        }
+        (twice_area - self.boundary_points() + 2) / 2
+    }
+
+    /// Number of integer points inside or on the edges, e.g. the tiles dug out by a dig plan.
+    pub fn lattice_points(&self) -> i64 {
+        self.interior_points() + self.boundary_points()
+    }
+}
+
+#[cfg(test)]
+mod test_polygon {
+    use super::Polygon;
+    use crate::utils::geom::{Direction, Point};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn rectangle() {
+        let rectangle = Polygon::new(vec![
+            Point::new(0, 0),
+            Point::new(4, 0),
+            Point::new(4, 3),
+            Point::new(0, 3),
+        ]);
+        k9::assert_equal!(rectangle.twice_signed_area(), 24);
+        k9::assert_equal!(rectangle.boundary_points(), 14);
+        k9::assert_equal!(rectangle.interior_points(), 6);
+        k9::assert_equal!(rectangle.lattice_points(), 20);
+
+        let reversed = Polygon::new(rectangle.vertices().iter().rev().copied().collect());
+        k9::assert_equal!(reversed.twice_signed_area(), -24);
+        k9::assert_equal!(reversed.interior_points(), 6);
+    }
+
+    #[test]
+    fn triangle_with_diagonal() {
+        let triangle = Polygon::new(vec![Point::new(0, 0), Point::new(4, 0), Point::new(0, 2)]);
+        k9::assert_equal!(triangle.twice_area(), 8);
+        // (0, 0), (2, 1) and (4, 0) on the diagonal, plus the other edges
+        k9::assert_equal!(triangle.boundary_points(), 8);
+        k9::assert_equal!(triangle.interior_points(), 1);
+    }
+
+    #[test]
+    fn degenerate() {
+        k9::assert_equal!(Polygon::new(vec![]).interior_points(), 0);
+        k9::assert_equal!(Polygon::new(vec![]).lattice_points(), 0);
+        let point = Polygon::new(vec![Point::new(2, 3)]);
+        k9::assert_equal!(point.interior_points(), 0);
+        let segment = Polygon::new(vec![Point::new(0, 0), Point::new(3, 0)]);
+        k9::assert_equal!(segment.twice_area(), 0);
+        k9::assert_equal!(segment.interior_points(), 0);
+        // the points of the segment, not counted again on the way back
+        k9::assert_equal!(segment.boundary_points(), 4);
+        k9::assert_equal!(segment.lattice_points(), 4);
+        k9::assert_equal!(point.lattice_points(), 1);
+        let flat = Polygon::new(vec![Point::new(0, 0), Point::new(2, 2), Point::new(4, 4)]);
+        k9::assert_equal!(flat.interior_points(), 0);
+        // (0, 0) to (4, 4) along the diagonal
+        k9::assert_equal!(flat.lattice_points(), 5);
+        let folded = Polygon::new(vec![Point::new(0, 0), Point::new(5, 0), Point::new(2, 0)]);
+        k9::assert_equal!(folded.lattice_points(), 6);
+    }
+
+    #[test]
+    fn dig_plan() {
+        use Direction::*;
+        // the example of 2023 day 18
+        let steps = [
+            (East, 6),
+            (South, 5),
+            (West, 2),
+            (South, 2),
+            (East, 2),
+            (South, 2),
+            (West, 5),
+            (North, 2),
+            (West, 1),
+            (North, 2),
+            (East, 2),
+            (North, 3),
+            (West, 2),
+            (North, 2),
+        ];
+        let lagoon = Polygon::from_steps(Point::ORIGIN, steps);
+        k9::assert_equal!(lagoon.vertices().len(), 14);
+        k9::assert_equal!(lagoon.boundary_points(), 38);
+        k9::assert_equal!(lagoon.lattice_points(), 62);
+    }
+
+    #[test]
+    fn matches_counting_cells() {
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            // a union of overlapping columns of cells, like the loops generated for day 10,
+            // whose outline goes around the corners of the cells
+            let heights = (0..rng.gen_range(1..8))
+                .map(|_| rng.gen_range(1..8))
+                .collect::<Vec<i64>>();
+            let mut vertices = vec![Point::ORIGIN];
+            for (x, &h) in heights.iter().enumerate() {
+                vertices.push(Point::new(x as i64, -h));
+                vertices.push(Point::new(x as i64 + 1, -h));
+            }
+            vertices.push(Point::new(heights.len() as i64, 0));
+            let polygon = Polygon::new(vertices);
+
+            let cells = heights.iter().sum::<i64>();
+            k9::assert_equal!(polygon.twice_area(), 2 * cells);
+            let perimeter = 2 * heights.len() as i64
+                + heights[0]
+                + heights.windows(2).map(|w| (w[0] - w[1]).abs()).sum::<i64>()
+                + heights[heights.len() - 1];
+            k9::assert_equal!(polygon.boundary_points(), perimeter);
+            // the corners of the cells strictly inside the outline are between two columns
+            let inside = heights.windows(2).map(|w| w[0].min(w[1]) - 1).sum::<i64>();
+            k9::assert_equal!(polygon.interior_points(), inside);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Add;
+
+/// How much work a search did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// States whose successors were looked at.
+    pub expanded: usize,
+    /// Distinct states reached, including the start.
+    pub discovered: usize,
+    /// Most states waiting to be expanded at once.
+    pub max_frontier: usize,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} expanded, {} discovered, frontier up to {}",
+            self.expanded, self.discovered, self.max_frontier
+        )
+    }
+}
+
+/// The outcome of a search: the goal it stopped at, if any, and the best known way to
+/// every state it reached on the way.
+#[derive(Debug, Clone)]
+pub struct Search<S, C> {
+    /// For each reached state, the state it was reached from and the cost to get there.
+    parents: HashMap<S, (Option<S>, C)>,
+    pub goal: Option<S>,
+    pub stats: Stats,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> Search<S, C> {
+    /// The cost to the goal, if one was found.
+    pub fn cost(&self) -> Option<C> {
+        self.cost_to(self.goal.as_ref()?)
+    }
+
+    /// The states from the start to the goal, both included, if a goal was found.
+    pub fn path(&self) -> Option<Vec<S>> {
+        self.path_to(self.goal.as_ref()?)
+    }
+
+    /// The best known cost to `state`. It's only guaranteed to be the lowest one for states
+    /// that were expanded, which the goal always is.
+    pub fn cost_to(&self, state: &S) -> Option<C> {
+        self.parents.get(state).map(|(_, cost)| *cost)
+    }
+
+    /// The states from the start to `state`, both included, if it was reached.
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        let mut path = vec![state.clone()];
+        let mut current = self.parents.get(state)?;
+        while let (Some(parent), _) = current {
+            path.push(parent.clone());
+            current = &self.parents[parent];
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Whether `state` was reached at all.
+    pub fn reached(&self, state: &S) -> bool {
+        self.parents.contains_key(state)
+    }
+}
+
+/// Breadth first search from `start` until a state is `is_goal`, where every step costs 1.
+pub fn bfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Search<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut search = Search {
+        parents: HashMap::from([(start.clone(), (None, 0))]),
+        goal: None,
+        stats: Stats::default(),
+    };
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((state, cost)) = queue.pop_front() {
+        if is_goal(&state) {
+            search.goal = Some(state);
+            break;
+        }
+        search.stats.expanded += 1;
+        for next in successors(&state) {
+            if !search.parents.contains_key(&next) {
+                search
+                    .parents
+                    .insert(next.clone(), (Some(state.clone()), cost + 1));
+                queue.push_back((next, cost + 1));
+            }
+        }
+        search.stats.max_frontier = search.stats.max_frontier.max(queue.len());
+    }
+    search.stats.discovered = search.parents.len();
+    search
+}
+
+/// Dijkstra's algorithm from `start` until a state is `is_goal`. The successors come with
+/// the cost of the step to them, which can't be negative.
+pub fn dijkstra<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Search<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(start, successors, |_| C::default(), is_goal)
+}
+
+/// A state waiting in the heap, the one with the lowest priority coming out first.
+struct Entry<S, C> {
+    priority: C,
+    cost: C,
+    state: S,
+}
+
+impl<S, C: Ord> PartialEq for Entry<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S, C: Ord> Eq for Entry<S, C> {}
+
+impl<S, C: Ord> PartialOrd for Entry<S, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S, C: Ord> Ord for Entry<S, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, as the heap pops the greatest first. On ties, prefer the one that went
+        // further, which is closer to the goal according to the heuristic
+        (&other.priority, &self.cost).cmp(&(&self.priority, &other.cost))
+    }
+}
+
+/// A* search from `start` until a state is `is_goal`. `heuristic` estimates the cost from
+/// a state to the goal, and must never overestimate it for the result to be the cheapest.
+/// With a heuristic of 0 this is [`dijkstra`].
+pub fn astar<S, C, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Search<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut search = Search {
+        parents: HashMap::from([(start.clone(), (None, C::default()))]),
+        goal: None,
+        stats: Stats::default(),
+    };
+    let mut heap = BinaryHeap::from([Entry {
+        priority: heuristic(&start),
+        cost: C::default(),
+        state: start,
+    }]);
+    while let Some(Entry { cost, state, .. }) = heap.pop() {
+        if search.parents[&state].1 < cost {
+            // a cheaper way to this state was found after this one was queued
+            continue;
+        }
+        if is_goal(&state) {
+            search.goal = Some(state);
+            break;
+        }
+        search.stats.expanded += 1;
+        for (next, step) in successors(&state) {
+            let cost = cost + step;
+            let better = match search.parents.get(&next) {
+                Some((_, known)) => cost < *known,
+                None => true,
+            };
+            if better {
+                search
+                    .parents
+                    .insert(next.clone(), (Some(state.clone()), cost));
+                heap.push(Entry {
+                    priority: cost + heuristic(&next),
+                    cost,
+                    state: next,
+                });
+            }
+        }
+        search.stats.max_frontier = search.stats.max_frontier.max(heap.len());
+    }
+    search.stats.discovered = search.parents.len();
+    search
+}
+
+#[cfg(test)]
+mod test_search {
+    use super::{astar, bfs, dijkstra};
+    use crate::utils::geom::Point;
+    use crate::utils::grid::Grid;
+
+    /// A weighted directed graph, where the direct edges are the most expensive way around.
+    ///
+    /// ```text
+    /// a -1-> b -1-> c -1-> d
+    ///  \-----5----->/      ^
+    ///   \---------10-------/     e is unreachable
+    /// ```
+    fn edges(node: &char) -> Vec<(char, u32)> {
+        match node {
+            'a' => vec![('b', 1), ('c', 5), ('d', 10)],
+            'b' => vec![('c', 1)],
+            'c' => vec![('d', 1)],
+            _ => vec![],
+        }
+    }
+
+    const MAZE: &str = "\
+S.#.....
+.##.###.
+....#...
+.####.#.
+......#G
+";
+
+    fn maze() -> (Grid<char>, Point, Point) {
+        let grid = MAZE.parse::<Grid<char>>().unwrap();
+        let start = Point::from_pos(grid.find(&'S').unwrap());
+        let goal = Point::from_pos(grid.find(&'G').unwrap());
+        (grid, start, goal)
+    }
+
+    fn open_neighbours(grid: &Grid<char>, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbours4().filter(|n| {
+            n.to_pos()
+                .and_then(|pos| grid.get(pos))
+                .is_some_and(|c| *c != '#')
+        })
+    }
+
+    #[test]
+    fn bfs_counts_steps() {
+        let search = bfs('a', |n| edges(n).into_iter().map(|(m, _)| m), |n| *n == 'd');
+        k9::assert_equal!(search.cost(), Some(1));
+        k9::assert_equal!(search.path(), Some(vec!['a', 'd']));
+        k9::assert_equal!(search.stats.expanded, 3);
+        k9::assert_equal!(search.stats.discovered, 4);
+    }
+
+    #[test]
+    fn dijkstra_finds_the_cheapest_path() {
+        let search = dijkstra('a', edges, |n| *n == 'd');
+        k9::assert_equal!(search.cost(), Some(3));
+        k9::assert_equal!(search.path(), Some(vec!['a', 'b', 'c', 'd']));
+        k9::assert_equal!(search.cost_to(&'c'), Some(2));
+        k9::assert_equal!(search.path_to(&'c'), Some(vec!['a', 'b', 'c']));
+        assert!(!search.reached(&'e'));
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let search = dijkstra('a', edges, |n| *n == 'e');
+        k9::assert_equal!(search.goal, None);
+        k9::assert_equal!(search.cost(), None);
+        k9::assert_equal!(search.path(), None);
+        // everything reachable was expanded before giving up
+        k9::assert_equal!(search.stats.expanded, 4);
+        k9::assert_equal!(search.stats.discovered, 4);
+
+        let search = bfs('b', |n| edges(n).into_iter().map(|(m, _)| m), |n| *n == 'a');
+        k9::assert_equal!(search.path(), None);
+        k9::assert_equal!(search.stats.discovered, 3);
+    }
+
+    #[test]
+    fn start_is_goal() {
+        let search = astar('a', edges, |_| 0, |n| *n == 'a');
+        k9::assert_equal!(search.cost(), Some(0));
+        k9::assert_equal!(search.path(), Some(vec!['a']));
+        k9::assert_equal!(search.stats.expanded, 0);
+    }
+
+    #[test]
+    fn maze_with_every_search() {
+        let (grid, start, goal) = maze();
+        let by_bfs = bfs(start, |p| open_neighbours(&grid, *p), |p| *p == goal);
+        let steps = |p: &Point| open_neighbours(&grid, *p).map(|n| (n, 1));
+        let by_dijkstra = dijkstra(start, steps, |p| *p == goal);
+        let by_astar = astar(start, steps, |p| p.manhattan(goal), |p| *p == goal);
+
+        k9::assert_equal!(by_bfs.cost(), Some(15));
+        k9::assert_equal!(by_dijkstra.cost(), Some(15));
+        k9::assert_equal!(by_astar.cost(), Some(15));
+
+        let path = by_astar.path().unwrap();
+        k9::assert_equal!(path.len(), 16);
+        k9::assert_equal!(path.first(), Some(&start));
+        k9::assert_equal!(path.last(), Some(&goal));
+        assert!(path.windows(2).all(|w| w[0].manhattan(w[1]) == 1));
+        assert!(by_astar.stats.expanded <= by_dijkstra.stats.expanded);
+    }
+}
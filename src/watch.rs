@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use colored::*;
+
+/// How often we look at the watched files for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The files that are relevant when working on `day`: its solution, its input and its examples.
+pub fn watched_paths(day: u8) -> Vec<PathBuf> {
+    vec![
+        PathBuf::from(format!("src/puzzles/day{day}.rs")),
+        PathBuf::from(format!("inputs/day_{day}.txt")),
+        PathBuf::from(format!("examples/day{day}")),
+    ]
+}
+
+/// Modification times of all the files under `paths`. Directories are walked
+/// recursively, and missing paths are skipped so they can be created later on.
+fn snapshot(paths: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut res = HashMap::new();
+    let mut stack: Vec<PathBuf> = paths.to_vec();
+    while let Some(path) = stack.pop() {
+        if path.is_dir() {
+            if let Ok(entries) = std::fs::read_dir(&path) {
+                stack.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()));
+            }
+        } else if let Ok(modified) = std::fs::metadata(&path).and_then(|m| m.modified()) {
+            res.insert(path, modified);
+        }
+    }
+    res
+}
+
+/// Waits for the changes to settle down before triggering, so that saving
+/// several times in a row (or an editor writing files in several steps)
+/// only triggers a single run.
+pub struct Debouncer {
+    delay: Duration,
+    pending_since: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending_since: None,
+        }
+    }
+
+    /// Record whether something `changed` at time `now`, returns true when we should trigger a run.
+    pub fn update(&mut self, changed: bool, now: Instant) -> bool {
+        if changed {
+            self.pending_since = Some(now);
+            return false;
+        }
+        match self.pending_since {
+            Some(since) if now.duration_since(since) >= self.delay => {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Pass / fail counts of the tests of a day, bucketed by kind.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TestSummary {
+    pub examples: (usize, usize),
+    pub real: (usize, usize),
+    pub other: (usize, usize),
+    pub failures: Vec<String>,
+}
+
+impl TestSummary {
+    /// Parse the output of libtest, e.g. `test puzzles::day5::test_day_5::day5_p1_example ... ok`
+    pub fn from_test_output(output: &str) -> Self {
+        let mut summary = TestSummary::default();
+        for line in output.lines() {
+            let Some(line) = line.strip_prefix("test ") else {
+                continue;
+            };
+            let Some((name, status)) = line.split_once(" ... ") else {
+                continue;
+            };
+            let passed = match status.trim() {
+                "ok" => true,
+                "FAILED" => false,
+                _ => continue, // ignored tests
+            };
+            let test_name = name.rsplit("::").next().unwrap_or(name);
+            let bucket = if test_name.contains("example") {
+                &mut summary.examples
+            } else if test_name.contains("real") || test_name.starts_with("test_part_") {
+                &mut summary.real
+            } else {
+                &mut summary.other
+            };
+            if passed {
+                bucket.0 += 1;
+            } else {
+                bucket.1 += 1;
+                summary.failures.push(test_name.to_string());
+            }
+        }
+        summary
+    }
+}
+
+fn format_counts(label: &str, (passed, failed): (usize, usize)) -> String {
+    let counts = format!("{label} {passed}/{}", passed + failed);
+    if failed > 0 {
+        counts.red().to_string()
+    } else {
+        counts.green().to_string()
+    }
+}
+
+/// Rebuild and run the tests and the solutions of `day`, and print a compact summary.
+fn check(day: u8) -> Result<()> {
+    let now = chrono::Local::now().format("%H:%M:%S");
+    let tests = Command::new("cargo")
+        .args(["test", "--lib", &format!("puzzles::day{day}::")])
+        .output()
+        .context("Error running cargo test")?;
+    let stdout = String::from_utf8_lossy(&tests.stdout);
+
+    if !stdout.contains("running ") {
+        // we did not even get to run the tests, so this is a build failure
+        println!("[{now}] day {day}: {}", "build failed".red());
+        String::from_utf8_lossy(&tests.stderr)
+            .lines()
+            .filter(|l| l.starts_with("error") || l.trim_start().starts_with("-->"))
+            .take(10)
+            .for_each(|l| println!("  {l}"));
+        return Ok(());
+    }
+
+    let summary = TestSummary::from_test_output(&stdout);
+    let mut line = vec![
+        format_counts("examples", summary.examples),
+        format_counts("real", summary.real),
+    ];
+    if summary.other != (0, 0) {
+        line.push(format_counts("other", summary.other));
+    }
+    println!("[{now}] day {day}: {}", line.join(" | "));
+    for failure in summary.failures.iter() {
+        println!("  {} {failure}", "FAILED".red());
+    }
+
+    let answers = Command::new("cargo")
+        .args(["run", "--quiet", "--", "run", &day.to_string()])
+        .output()
+        .context("Error running the solutions")?;
+    String::from_utf8_lossy(&answers.stdout)
+        .lines()
+        .for_each(|l| println!("  {l}"));
+    Ok(())
+}
+
+/// Watch the files for `day`, and rerun its checks whenever they change.
+pub fn watch(day: u8, debounce: Duration) -> Result<()> {
+    let paths = watched_paths(day);
+    let display_paths = paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>();
+    println!("Watching {}", display_paths.join(", "));
+
+    let mut last_snapshot = snapshot(&paths);
+    let mut debouncer = Debouncer::new(debounce);
+    check(day)?;
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let current = snapshot(&paths);
+        let changed = current != last_snapshot;
+        last_snapshot = current;
+        if debouncer.update(changed, Instant::now()) {
+            check(day)?;
+        }
+    }
+}
+
+/// Whether we're running from the root of the repo, where the sources can be found.
+pub fn in_repo_root() -> bool {
+    Path::new("Cargo.toml").is_file() && Path::new("src/puzzles").is_dir()
+}
+
+#[cfg(test)]
+mod test_watch {
+    use super::{Debouncer, TestSummary};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn debouncer_waits_for_changes_to_settle() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut debouncer = Debouncer::new(Duration::from_millis(300));
+
+        assert!(!debouncer.update(false, ms(0)));
+        assert!(!debouncer.update(true, ms(100)));
+        assert!(!debouncer.update(true, ms(200)));
+        assert!(!debouncer.update(false, ms(400)));
+        assert!(debouncer.update(false, ms(500)));
+        // only triggers once per burst of changes
+        assert!(!debouncer.update(false, ms(900)));
+    }
+
+    #[test]
+    fn summarize_test_output() {
+        let output = "\
+running 5 tests
+test puzzles::day5::test_day_5::day5_p1_example ... ok
+test puzzles::day5::test_day_5::day5_p2_example ... FAILED
+test puzzles::day5::test_day_5::day5_p1_real ... ok
+test puzzles::day5::test_day_5::day5_p2_real ... ok
+test puzzles::day5::test_day_5::day5_parse ... ok
+
+test result: FAILED. 4 passed; 1 failed; 0 ignored; 0 measured; 78 filtered out";
+        let summary = TestSummary::from_test_output(output);
+        k9::assert_equal!(
+            summary,
+            TestSummary {
+                examples: (1, 1),
+                real: (2, 0),
+                other: (1, 0),
+                failures: vec!["day5_p2_example".to_string()],
+            }
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! Checks that all the implementations of each part agree on the examples and on generated inputs.
+//! The puzzle inputs are left to `cargo run --release -- crosscheck`, as brute force versions can be slow on them.
+
+use std::path::Path;
+
+use advent_of_code_2023::crosscheck::{crosscheck, Options};
+use advent_of_code_2023::registry::DAYS;
+
+#[test]
+fn implementations_agree() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let options = Options {
+        real: false,
+        examples: Some(root.join("examples")),
+        seeds: 50,
+        size: 8,
+    };
+    let out = root.join("target").join("crosscheck");
+    let failures = DAYS
+        .iter()
+        .flat_map(|day| crosscheck(day, &options).unwrap())
+        .map(|disagreement| {
+            let path = disagreement.save(&out).unwrap();
+            format!("{disagreement}\n  input saved to {}", path.display())
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        failures.is_empty(),
+        "{} disagreements:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,45 @@
+//! Runs every example under `examples/dayN/` against the solutions.
+//! To add a case, drop in `{name}.txt` and its answer in `part1.toml` or `part2.toml`.
+
+use std::path::Path;
+
+use advent_of_code_2023::fixtures;
+use advent_of_code_2023::registry;
+
+#[test]
+fn all_examples() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let all_fixtures = fixtures::discover(&root).unwrap();
+    assert!(
+        !all_fixtures.is_empty(),
+        "No examples found in {}",
+        root.display()
+    );
+    // days compiled out with their feature can't be checked, which can be all of them
+    let fixtures = all_fixtures
+        .into_iter()
+        .filter(|fixture| registry::get_day(fixture.day).is_some())
+        .collect::<Vec<_>>();
+    if registry::DAYS.is_empty() {
+        return;
+    }
+
+    let failures = fixtures
+        .iter()
+        .filter_map(|fixture| {
+            let res = fixture.check();
+            println!(
+                "{fixture} ... {}",
+                if res.is_ok() { "ok" } else { "FAILED" }
+            );
+            res.err().map(|e| format!("{fixture}: {e}"))
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        failures.is_empty(),
+        "{} of {} examples failed:\n{}",
+        failures.len(),
+        fixtures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,66 @@
+//! Calls the solutions through the C API, the same way a program embedding the library would.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+// make sure the library, and the symbols it exports, are linked in
+extern crate advent_of_code_2023;
+
+extern "C" {
+    fn aoc_solve(year: u16, day: u8, part: u8, input: *const c_char, out: *mut *mut c_char) -> i32;
+    fn aoc_string_free(s: *mut c_char);
+}
+
+#[cfg(feature = "day6")]
+const AOC_OK: i32 = 0;
+const AOC_ERROR: i32 = 1;
+const AOC_INVALID_ARGUMENT: i32 = 2;
+
+/// Call `aoc_solve` and take ownership of the string it returns.
+fn call_solve(year: u16, day: u8, part: u8, input: &str) -> (i32, String) {
+    let input = CString::new(input).unwrap();
+    let mut out: *mut c_char = ptr::null_mut();
+    unsafe {
+        let status = aoc_solve(year, day, part, input.as_ptr(), &mut out);
+        let message = CStr::from_ptr(out).to_str().unwrap().to_string();
+        aoc_string_free(out);
+        (status, message)
+    }
+}
+
+#[test]
+#[cfg(feature = "day6")]
+fn ffi_solve_example() {
+    let input = "Time:      7  15   30\nDistance:  9  40  200\n";
+    assert_eq!(call_solve(2023, 6, 1, input), (AOC_OK, "288".to_string()));
+    assert_eq!(call_solve(2023, 6, 2, input), (AOC_OK, "71503".to_string()));
+}
+
+#[test]
+fn ffi_solve_errors() {
+    let (status, message) = call_solve(2023, 30, 1, "");
+    assert_eq!(status, AOC_ERROR);
+    assert_eq!(message, "Day 30 is not solved");
+
+    let (status, _) = call_solve(2023, 9, 1, "not numbers");
+    assert_eq!(status, AOC_ERROR);
+}
+
+#[test]
+fn ffi_invalid_arguments() {
+    let mut out: *mut c_char = ptr::null_mut();
+    let invalid_utf8 = [0xffu8, 0xfe, 0];
+    unsafe {
+        assert_eq!(
+            aoc_solve(2023, 1, 1, ptr::null(), &mut out),
+            AOC_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            aoc_solve(2023, 1, 1, invalid_utf8.as_ptr() as *const c_char, &mut out),
+            AOC_INVALID_ARGUMENT
+        );
+        assert!(out.is_null());
+        // freeing null is allowed
+        aoc_string_free(out);
+    }
+}
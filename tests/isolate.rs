@@ -0,0 +1,79 @@
+//! Runs solutions in their own process, mostly through the CLI, checking that the ways they can fail
+//! are told apart and don't take the caller down.
+#![cfg(all(feature = "day9", feature = "day16"))]
+
+use std::process::Command;
+use std::time::Duration;
+
+use advent_of_code_2023::isolate::{report, run_in_subprocess, Status};
+use advent_of_code_2023::registry::get_day;
+
+fn isolated(day: u8, part: u8) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_advent_of_code_2023"));
+    command.args(["isolated", &day.to_string(), &part.to_string()]);
+    command
+}
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[test]
+fn solved() {
+    let input = "0 3 6 9 12 15\n1 3 6 10 15 21\n10 13 16 21 30 45\n";
+    let status = run_in_subprocess(isolated(9, 1), input, TIMEOUT).unwrap();
+    assert!(
+        matches!(&status, Status::Solved { answer, .. } if answer == "114"),
+        "{status}"
+    );
+}
+
+#[test]
+fn panicked() {
+    let status = run_in_subprocess(isolated(9, 1), "not numbers", TIMEOUT).unwrap();
+    assert!(matches!(status, Status::Panicked(_)), "{status}");
+}
+
+/// Set in the environment of [`overflowing_child`] when it runs as the child process.
+const OVERFLOW_CHILD: &str = "AOC_OVERFLOW_CHILD";
+
+/// Recurse until the stack overflows, whatever its size or the build profile.
+fn overflow(depth: usize) -> usize {
+    if depth == usize::MAX {
+        return 0;
+    }
+    // a frame the compiler can't optimize away, and no tail call to turn into a loop
+    let frame = std::hint::black_box([depth; 32]);
+    overflow(depth + 1) + frame[0]
+}
+
+/// Not a test on its own: the process `stack_overflow` runs, which reports like the
+/// `isolated` command of the CLI.
+#[test]
+fn overflowing_child() {
+    if std::env::var_os(OVERFLOW_CHILD).is_none() {
+        return;
+    }
+    report(|input| overflow(input.len()).into(), "", false).unwrap();
+}
+
+#[test]
+fn stack_overflow() {
+    let mut command = Command::new(std::env::current_exe().unwrap());
+    command
+        .args([
+            "overflowing_child",
+            "--exact",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env(OVERFLOW_CHILD, "1");
+    let status = run_in_subprocess(command, "", TIMEOUT).unwrap();
+    k9::assert_equal!(status, Status::StackOverflow);
+}
+
+#[test]
+fn timed_out() {
+    let input = get_day(16).unwrap().generate_input(0, 100);
+    let timeout = Duration::from_millis(1);
+    let status = run_in_subprocess(isolated(16, 2), &input, timeout).unwrap();
+    k9::assert_equal!(status, Status::TimedOut(timeout));
+}
@@ -0,0 +1,64 @@
+//! Serves the solutions with each one running in its own process of the CLI, checking that
+//! the ones timing out are killed and don't hold up the service.
+#![cfg(all(feature = "day9", feature = "day16"))]
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use advent_of_code_2023::registry::get_day;
+use advent_of_code_2023::serve::{bind, serve, Limits, Worker};
+use serde_json::{json, Value};
+
+#[test]
+fn solutions_in_processes() {
+    let server = bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+    let limits = Limits {
+        max_input_bytes: 1024 * 1024,
+        timeout: Duration::from_secs(5),
+        max_in_flight: 1,
+        worker: Worker::Process(PathBuf::from(env!("CARGO_BIN_EXE_advent_of_code_2023"))),
+    };
+    std::thread::spawn(move || serve(server, limits));
+    let client = reqwest::blocking::Client::new();
+    let solve = |day: u8, input: String| {
+        client
+            .post(format!("{base}/2023/day/{day}/part/1"))
+            .body(input)
+            .send()
+            .unwrap()
+    };
+
+    let response = solve(9, "0 3 6 9 12 15\n1 3 6 10 15 21\n".to_string());
+    k9::assert_equal!(response.status().as_u16(), 200);
+    let solved: Value = response.json().unwrap();
+    k9::assert_equal!(solved["answer"], json!(46));
+
+    k9::assert_equal!(solve(9, "not numbers".to_string()).status().as_u16(), 500);
+}
+
+#[test]
+fn timed_out_processes_are_killed() {
+    let server = bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+    let limits = Limits {
+        max_input_bytes: 1024 * 1024,
+        timeout: Duration::from_millis(1),
+        max_in_flight: 1,
+        worker: Worker::Process(PathBuf::from(env!("CARGO_BIN_EXE_advent_of_code_2023"))),
+    };
+    std::thread::spawn(move || serve(server, limits));
+    let client = reqwest::blocking::Client::new();
+
+    let input = get_day(16).unwrap().generate_input(0, 100);
+    // with a single solution allowed at a time, each request would be turned away if the
+    // previous solution were still running
+    for _ in 0..3 {
+        let response = client
+            .post(format!("{base}/2023/day/16/part/2"))
+            .body(input.clone())
+            .send()
+            .unwrap();
+        k9::assert_equal!(response.status().as_u16(), 504);
+    }
+}